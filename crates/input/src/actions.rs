@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{structs::*, InputPressedEvent, InputDepressedEvent, InputChangedEvent, update_inputs};
+
+// A type whose values map to action names, so actions can be checked at compile time instead of using strings
+// usually created with the input_actions! macro
+pub trait InputAction: Copy + Eq + Send + Sync + std::fmt::Debug + 'static {
+    // the action name used in input descriptions
+    fn name(&self) -> &'static str;
+
+    // every action of this type
+    fn all() -> &'static [Self];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::all().iter().find(|action| action.name() == name).copied()
+    }
+}
+
+// create an enum that implements InputAction
+// input_actions! { pub enum PlayerAction { Jump = "jump", Fire = "fire" } }
+#[macro_export]
+macro_rules! input_actions {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident { $($variant:ident = $action:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $name { $($variant),* }
+
+        impl $crate::actions::InputAction for $name {
+            fn name(&self) -> &'static str {
+                match self { $($name::$variant => $action),* }
+            }
+
+            fn all() -> &'static [Self] { &[$($name::$variant),*] }
+        }
+    };
+}
+
+// plugin that sends typed events for actions of the given type
+pub struct InputActionPlugin<A: InputAction>(PhantomData<A>);
+impl<A: InputAction> Default for InputActionPlugin<A> {
+    fn default() -> Self { Self(PhantomData) }
+}
+
+impl<A: InputAction> Plugin for InputActionPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<InputActionPressedEvent<A>>()
+            .add_event::<InputActionDepressedEvent<A>>()
+            .add_event::<InputActionChangedEvent<A>>()
+            .add_systems(Update, send_action_events::<A>.after(update_inputs));
+    }
+}
+
+// events
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputActionPressedEvent<A: InputAction> { pub entity: Entity, pub action: A, pub value: f32 }
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputActionDepressedEvent<A: InputAction> { pub entity: Entity, pub action: A, pub value: f32 }
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputActionChangedEvent<A: InputAction> { pub entity: Entity, pub action: A, pub value: f32 }
+
+fn send_action_events<A: InputAction>(
+    mut pressed: EventReader<InputPressedEvent>,
+    mut depressed: EventReader<InputDepressedEvent>,
+    mut changed: EventReader<InputChangedEvent>,
+    mut pressed_actions: EventWriter<InputActionPressedEvent<A>>,
+    mut depressed_actions: EventWriter<InputActionDepressedEvent<A>>,
+    mut changed_actions: EventWriter<InputActionChangedEvent<A>>
+) {
+    pressed.read().for_each(|event| {
+        let action = A::from_name(&event.name);
+        if action.is_some() { pressed_actions.send(InputActionPressedEvent { entity: event.entity, action: action.unwrap(), value: event.value }); }
+    });
+    depressed.read().for_each(|event| {
+        let action = A::from_name(&event.name);
+        if action.is_some() { depressed_actions.send(InputActionDepressedEvent { entity: event.entity, action: action.unwrap(), value: event.value }); }
+    });
+    changed.read().for_each(|event| {
+        let action = A::from_name(&event.name);
+        if action.is_some() { changed_actions.send(InputActionChangedEvent { entity: event.entity, action: action.unwrap(), value: event.value }); }
+    });
+}
+
+impl Inputs {
+    pub fn get_action<A: InputAction>(&self, action: A) -> f32 {
+        *self.values.get(action.name()).unwrap_or(&0.0)
+    }
+
+    pub fn get_action_vec2<A: InputAction>(&self, action: A) -> Vec2 {
+        *self.vectors.get(action.name()).unwrap_or(&Vec2::ZERO)
+    }
+
+    pub fn is_action_pressed<A: InputAction>(&self, action: A) -> bool { self.get_action(action) >= 1. }
+}
+
+impl InputDescription {
+    pub fn insert_action<A: InputAction>(&mut self, action: A, inputs: Vec<InputType>) -> &mut Self {
+        self.insert(action.name(), inputs)
+    }
+
+    // get all actions of the given type that have no inputs in this description
+    pub fn missing_actions<A: InputAction>(&self) -> Vec<A> {
+        A::all().iter().filter(|action| !self.elements.contains_key(action.name())).copied().collect()
+    }
+}
@@ -0,0 +1,46 @@
+use std::path::{Path, PathBuf};
+
+use nebulousengine_input::validation::{parse_input_description, input_description_warnings};
+
+// checks every .input file under the given directory (assets by default), exiting with an error if any fail to parse
+// cargo run --manifest-path crates/input/Cargo.toml --bin validate_inputs -- assets
+fn main() {
+    let root = std::env::args().nth(1).unwrap_or("assets".into());
+    let mut files = Vec::new();
+    find_input_files(Path::new(&root), &mut files);
+    files.sort();
+
+    let mut failed = 0;
+    files.iter().for_each(|path| {
+        let content = std::fs::read_to_string(path);
+        let content = if content.is_ok() { content.unwrap() } else {
+            println!("error: {}: could not read file", path.display());
+            failed += 1;
+            return
+        };
+
+        match parse_input_description(&content) {
+            Ok(description) => {
+                input_description_warnings(&content, &description).iter().for_each(|warning| println!("warning: {}: {warning}", path.display()));
+                println!("ok: {}", path.display());
+            },
+            Err(error) => {
+                println!("error: {}: {error}", path.display());
+                failed += 1;
+            }
+        }
+    });
+
+    println!("Checked {} input files, {failed} failed", files.len());
+    if failed > 0 { std::process::exit(1); }
+}
+
+fn find_input_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = std::fs::read_dir(dir);
+    let entries = if entries.is_ok() { entries.unwrap() } else { return };
+    entries.filter_map(|entry| entry.ok()).for_each(|entry| {
+        let path = entry.path();
+        if path.is_dir() { find_input_files(&path, files); }
+        else if path.extension().is_some_and(|extension| extension == "input") { files.push(path); }
+    });
+}
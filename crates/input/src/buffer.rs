@@ -0,0 +1,59 @@
+use crate::structs::Inputs;
+
+// A press or release of an action at a point in time, in seconds since startup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputTransition {
+    pub pressed: bool,
+    pub time: f32,
+    pub consumed: bool
+}
+
+impl Inputs {
+    // set how many seconds of presses and releases are kept
+    pub fn with_buffer_time(mut self, seconds: f32) -> Self {
+        self.buffer_time = seconds;
+        self
+    }
+
+    // get the kept presses and releases of an action, oldest first
+    pub fn history(&self, name: &str) -> impl Iterator<Item = &InputTransition> {
+        self.history.get(name).into_iter().flatten()
+    }
+
+    // check if an action was pressed within the last window seconds and that press has not been consumed
+    pub fn buffered_pressed(&self, name: &str, window: f32) -> bool {
+        self.history(name).any(|transition| transition.pressed && !transition.consumed && self.now - transition.time <= window)
+    }
+
+    // check if an action was released within the last window seconds
+    pub fn buffered_released(&self, name: &str, window: f32) -> bool {
+        self.history(name).any(|transition| !transition.pressed && self.now - transition.time <= window)
+    }
+
+    // use up the newest buffered press within the window so it only triggers once, returns if there was one
+    pub fn consume_buffered(&mut self, name: &str, window: f32) -> bool {
+        let now = self.now;
+        let history = self.history.get_mut(name);
+        let history = if history.is_some() { history.unwrap() } else { return false };
+        let transition = history.iter_mut().rev()
+            .find(|transition| transition.pressed && !transition.consumed && now - transition.time <= window);
+        if transition.is_none() { return false }
+        transition.unwrap().consumed = true;
+        true
+    }
+
+    // save a press or release, prune_history forgets ones older than the buffer time
+    pub(crate) fn record_transition(&mut self, name: &str, pressed: bool, now: f32) {
+        let history = self.history.entry(name.to_string()).or_default();
+        history.push_back(InputTransition { pressed, time: now, consumed: false });
+    }
+
+    // forget presses and releases older than the buffer time
+    pub(crate) fn prune_history(&mut self, now: f32) {
+        self.now = now;
+        let buffer_time = self.buffer_time;
+        self.history.values_mut().for_each(|history| {
+            while history.front().is_some_and(|transition| now - transition.time > buffer_time) { history.pop_front(); }
+        });
+    }
+}
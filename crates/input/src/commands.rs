@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use nebulousengine_utils::console::{ConsoleCommand, ConsoleCommandPlugin};
+
+use crate::structs::Inputs;
+
+// plugin that adds the "input" console command
+pub struct InputCommands;
+impl Plugin for InputCommands {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(ConsoleCommandPlugin::<InputCommand>::default())
+            .add_systems(Update, handle_input_commands);
+    }
+}
+
+// console command to inspect inputs (ex. "input get jump")
+#[derive(Event, Debug, Clone)]
+pub enum InputCommand {
+    List,
+    Get(String)
+}
+
+impl ConsoleCommand for InputCommand {
+    fn name() -> &'static str { "input" }
+    fn description() -> &'static str { "\"input list\" prints all input values, \"input get <name>\" prints one" }
+
+    fn parse(args: &[String]) -> Result<Self, String> {
+        match args.first().map(|a| a.as_str()) {
+            Some("list") | None => Ok(Self::List),
+            Some("get") => {
+                let name = args.get(1);
+                if name.is_some() { Ok(Self::Get(name.unwrap().clone())) } else { Err("Usage: input get <name>".into()) }
+            },
+            Some(other) => Err(format!("Unknown input subcommand {other}"))
+        }
+    }
+
+    fn complete(args: &[String]) -> Vec<String> {
+        if args.is_empty() { vec!["list".into(), "get".into()] } else { Vec::new() }
+    }
+}
+
+fn handle_input_commands(
+    mut events: EventReader<InputCommand>,
+    inputs: Query<(Entity, &Inputs)>
+) {
+    events.read().for_each(|event| {
+        match event {
+            InputCommand::List => {
+                if inputs.is_empty() { info!("No inputs"); }
+                inputs.for_each(|(entity, inputs)| {
+                    let mut values: Vec<String> = inputs.values.iter().map(|(name, value)| format!("{name}={value}")).collect();
+                    values.sort();
+                    info!("{:?} ({:?}): {}", entity, inputs.device, values.join(", "));
+                });
+            },
+            InputCommand::Get(name) => {
+                inputs.for_each(|(entity, inputs)| info!("{:?}: {}={}", entity, name, inputs.get(name)));
+            }
+        }
+    });
+}
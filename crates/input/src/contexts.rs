@@ -0,0 +1,117 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::structs::*;
+
+// plugin that sets up global input claims, contexts themselves live on each inputs component
+pub struct InputContextsPlugin;
+impl Plugin for InputContextsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputClaims>();
+    }
+}
+
+// What a context hides from the contexts below it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputConsume {
+    // lower contexts still see everything
+    #[default]
+    None,
+    // only the keys, buttons and axes bound in the contexts description
+    Used,
+    Keyboard,
+    Mouse,
+    KeyboardMouse,
+    Gamepads,
+    All
+}
+
+impl InputConsume {
+    pub fn keyboard(&self) -> bool { matches!(self, InputConsume::Keyboard | InputConsume::KeyboardMouse | InputConsume::All) }
+    pub fn mouse(&self) -> bool { matches!(self, InputConsume::Mouse | InputConsume::KeyboardMouse | InputConsume::All) }
+    pub fn gamepads(&self) -> bool { matches!(self, InputConsume::Gamepads | InputConsume::All) }
+}
+
+// A layer of actions on top of an inputs base description (ex. a pause menu), higher priorities are read first
+#[derive(Debug, Clone)]
+pub struct InputContext {
+    pub name: String,
+    pub description: InputDescriptionContainer,
+    pub priority: i32,
+    pub consume: InputConsume
+}
+
+impl InputContext {
+    pub fn from_handle(name: impl Into<String>, handle: Handle<InputDescription>, priority: i32) -> Self {
+        Self { name: name.into(), description: InputDescriptionContainer::Handle(handle), priority, consume: InputConsume::None }
+    }
+
+    pub fn from_description(name: impl Into<String>, description: InputDescription, priority: i32) -> Self {
+        Self { name: name.into(), description: InputDescriptionContainer::Raw(description), priority, consume: InputConsume::None }
+    }
+
+    pub fn new<F>(name: impl Into<String>, priority: i32, f: F) -> Self where F: Fn(&mut InputDescription) {
+        Self::from_description(name, InputDescription::create(f), priority)
+    }
+
+    pub fn with_consume(mut self, consume: InputConsume) -> Self {
+        self.consume = consume;
+        self
+    }
+}
+
+// resource of devices claimed away from every inputs component, keyed by who claimed them (ex. a focused text area)
+#[derive(Resource, Debug, Default, Clone)]
+pub struct InputClaims {
+    pub claims: HashMap<String, InputConsume>
+}
+
+impl InputClaims {
+    pub fn claim(&mut self, owner: impl Into<String>, consume: InputConsume) {
+        self.claims.insert(owner.into(), consume);
+    }
+
+    pub fn release(&mut self, owner: &str) {
+        self.claims.remove(owner);
+    }
+
+    pub fn is_claimed(&self, owner: &str) -> bool { self.claims.contains_key(owner) }
+}
+
+// everything consumed so far while reading an inputs contexts from the top down
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ConsumedInputs {
+    keyboard: bool,
+    mouse: bool,
+    gamepads: bool,
+    elements: Vec<InputElement>
+}
+
+impl ConsumedInputs {
+    pub(crate) fn from_claims(claims: &InputClaims) -> Self {
+        let mut consumed = Self::default();
+        claims.claims.values().for_each(|consume| consumed.add(consume, None));
+        consumed
+    }
+
+    // add what a context consumes, used elements are only known if a description is given
+    pub(crate) fn add(&mut self, consume: &InputConsume, description: Option<&InputDescription>) {
+        self.keyboard |= consume.keyboard();
+        self.mouse |= consume.mouse();
+        self.gamepads |= consume.gamepads();
+        if *consume != InputConsume::Used || description.is_none() { return }
+        self.elements.extend(description.unwrap().elements.values().flatten().flat_map(|input_type| input_type.elements()).cloned());
+    }
+
+    // check if an element has been consumed by a higher context
+    pub(crate) fn blocks(&self, element: &InputElement) -> bool {
+        let blocked = match element.source() {
+            InputElement::Keyboard { .. } => self.keyboard,
+            InputElement::Mouse { .. } | InputElement::MouseMotion { .. } |
+            InputElement::MouseWheel { .. } | InputElement::CursorPosition { .. } |
+            InputElement::TouchButton { .. } | InputElement::VirtualJoystick { .. } => self.mouse,
+            InputElement::GamepadButton { .. } | InputElement::AnalogGamepadButton { .. } | InputElement::GamepadAxis { .. } => self.gamepads,
+            InputElement::Modified { .. } => false
+        };
+        blocked || self.elements.iter().any(|other| other.same_source(element))
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+use crate::structs::{Inputs, InputDevice};
+
+// plugin that automatically gives gamepads to players as they connect and disconnect
+pub struct InputDevicesPlugin;
+impl Plugin for InputDevicesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<InputGamepadAssignedEvent>()
+            .add_event::<InputGamepadUnassignedEvent>()
+            .add_systems(Update, assign_gamepads);
+    }
+}
+
+// marks an inputs component that should automatically be given a free gamepad, lower players get gamepads first
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct AutoAssignGamepad {
+    pub player: usize,
+    // device used while no gamepad is assigned
+    pub fallback: InputDevice
+}
+
+impl AutoAssignGamepad {
+    pub fn new(player: usize) -> Self { Self { player, fallback: InputDevice::None } }
+    pub fn with_fallback(player: usize, fallback: InputDevice) -> Self { Self { player, fallback } }
+}
+
+// events
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputGamepadAssignedEvent { pub entity: Entity, pub gamepad: Gamepad }
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputGamepadUnassignedEvent { pub entity: Entity, pub gamepad: Gamepad }
+
+pub(crate) fn assign_gamepads(
+    gamepads: Res<Gamepads>,
+    mut auto: Query<(Entity, &mut Inputs, &AutoAssignGamepad)>,
+    manual: Query<&Inputs, Without<AutoAssignGamepad>>,
+    mut assigned_events: EventWriter<InputGamepadAssignedEvent>,
+    mut unassigned_events: EventWriter<InputGamepadUnassignedEvent>
+) {
+    // unassign gamepads that disconnected
+    auto.for_each_mut(|(entity, mut inputs, auto)| {
+        let gamepad = match inputs.device { InputDevice::Gamepad(gamepad) => gamepad, _ => return };
+        if gamepads.contains(gamepad) { return }
+        inputs.device = auto.fallback;
+        unassigned_events.send(InputGamepadUnassignedEvent { entity, gamepad });
+    });
+
+    // get gamepads that nobody is using
+    let taken: Vec<Gamepad> = manual.iter().chain(auto.iter().map(|(_, inputs, _)| inputs))
+        .filter_map(|inputs| match inputs.device { InputDevice::Gamepad(gamepad) => Some(gamepad), _ => None })
+        .collect();
+    let mut free: Vec<Gamepad> = gamepads.iter().filter(|gamepad| !taken.contains(gamepad)).collect();
+    if free.is_empty() { return }
+    free.sort_by_key(|gamepad| gamepad.id);
+
+    // get players without a gamepad, in player order
+    let mut waiting: Vec<(Entity, usize)> = auto.iter()
+        .filter(|(_, inputs, _)| !matches!(inputs.device, InputDevice::Gamepad(_)))
+        .map(|(entity, _, auto)| (entity, auto.player))
+        .collect();
+    waiting.sort_by_key(|(_, player)| *player);
+
+    // give each waiting player a free gamepad
+    waiting.iter().zip(free.iter()).for_each(|((entity, _), gamepad)| {
+        let (_, mut inputs, _) = auto.get_mut(*entity).unwrap();
+        inputs.device = InputDevice::Gamepad(*gamepad);
+        assigned_events.send(InputGamepadAssignedEvent { entity: *entity, gamepad: *gamepad });
+    });
+}
@@ -1,20 +1,27 @@
 use bevy::{prelude::*, reflect::Enum};
 use serde::*;
 
+// other axes keep their index, like "Other(4)"
 pub fn serialize<S>(button: &GamepadAxisType, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-    serializer.serialize_str(button.variant_name())
+    match button {
+        GamepadAxisType::Other(index) => serializer.serialize_str(&format!("Other({index})")),
+        _ => serializer.serialize_str(button.variant_name())
+    }
 }
 
 pub fn deserialize<'de, D>(deserializer: D) -> Result<GamepadAxisType, D::Error> where D: Deserializer<'de> {
     let value = String::deserialize(deserializer)?;
     let value = value.as_str();
     match value {
-        "LeftX" => Ok(GamepadAxisType::LeftStickX),
-        "LeftY" => Ok(GamepadAxisType::LeftStickY),
+        // short names are kept for older files
+        "LeftStickX" | "LeftX" => Ok(GamepadAxisType::LeftStickX),
+        "LeftStickY" | "LeftY" => Ok(GamepadAxisType::LeftStickY),
         "LeftZ" => Ok(GamepadAxisType::LeftZ),
-        "RightX" => Ok(GamepadAxisType::RightStickX),
-        "RightY" => Ok(GamepadAxisType::RightStickY),
+        "RightStickX" | "RightX" => Ok(GamepadAxisType::RightStickX),
+        "RightStickY" | "RightY" => Ok(GamepadAxisType::RightStickY),
         "RightZ" => Ok(GamepadAxisType::RightZ),
-        _ => { error!("No mouse button registered with value {value}"); Ok(GamepadAxisType::LeftStickX) }
+        _ if value.starts_with("Other(") && value.ends_with(')') => value[6 .. value.len() - 1].parse().map(GamepadAxisType::Other)
+            .map_err(|_| de::Error::custom(format!("invalid gamepad axis index in \"{value}\""))),
+        _ => Err(de::Error::custom(format!("unknown gamepad axis \"{value}\"")))
     }
 }
\ No newline at end of file
@@ -1,8 +1,12 @@
 use bevy::{prelude::*, reflect::Enum};
 use serde::*;
 
+// other buttons keep their index, like "Other(4)"
 pub fn serialize<S>(button: &GamepadButtonType, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-    serializer.serialize_str(button.variant_name())
+    match button {
+        GamepadButtonType::Other(index) => serializer.serialize_str(&format!("Other({index})")),
+        _ => serializer.serialize_str(button.variant_name())
+    }
 }
 
 pub fn deserialize<'de, D>(deserializer: D) -> Result<GamepadButtonType, D::Error> where D: Deserializer<'de> {
@@ -28,6 +32,8 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<GamepadButtonType, D::Erro
         "DPadDown" => Ok(GamepadButtonType::DPadDown),
         "DPadLeft" => Ok(GamepadButtonType::DPadLeft),
         "DPadRight" => Ok(GamepadButtonType::DPadRight),
-        _ => { error!("No mouse button registered with value {value}"); Ok(GamepadButtonType::East) }
+        _ if value.starts_with("Other(") && value.ends_with(')') => value[6 .. value.len() - 1].parse().map(GamepadButtonType::Other)
+            .map_err(|_| de::Error::custom(format!("invalid gamepad button index in \"{value}\""))),
+        _ => Err(de::Error::custom(format!("unknown gamepad button \"{value}\"")))
     }
 }
\ No newline at end of file
@@ -0,0 +1,172 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::*;
+
+use crate::{structs::*, update_inputs};
+
+// plugin that turns input values into interaction events like holds and double taps
+pub struct InputInteractionsPlugin;
+impl Plugin for InputInteractionsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<InputThresholdPressedEvent>()
+            .add_event::<InputReleasedEvent>()
+            .add_event::<InputTapEvent>()
+            .add_event::<InputHoldEvent>()
+            .add_event::<InputMultiTapEvent>()
+            .add_systems(Update, update_interactions.after(update_inputs));
+    }
+}
+
+// Interactions that can be added to a binding of an action, all times are in seconds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum InputInteraction {
+    // fires when the value reaches the threshold
+    Press {
+        #[serde(default = "threshold_default")]
+        threshold: f32
+    },
+    // fires when the value drops below the threshold, with how long it was held (ex. charge attacks)
+    Release {
+        #[serde(default = "threshold_default")]
+        threshold: f32
+    },
+    // fires when pressed and released within max_time
+    Tap {
+        #[serde(default = "tap_time_default")]
+        max_time: f32,
+        #[serde(default = "threshold_default")]
+        threshold: f32
+    },
+    // fires once when held for the duration
+    Hold {
+        duration: f32,
+        #[serde(default = "threshold_default")]
+        threshold: f32
+    },
+    // fires when tapped count times with at most window between taps (ex. double tap to dodge)
+    MultiTap {
+        #[serde(default = "count_default")]
+        count: usize,
+        #[serde(default = "tap_time_default")]
+        window: f32,
+        #[serde(default = "threshold_default")]
+        threshold: f32
+    }
+}
+
+impl InputInteraction {
+    pub fn threshold(&self) -> f32 {
+        match self {
+            InputInteraction::Press { threshold } |
+            InputInteraction::Release { threshold } |
+            InputInteraction::Tap { threshold, .. } |
+            InputInteraction::Hold { threshold, .. } |
+            InputInteraction::MultiTap { threshold, .. } => *threshold
+        }
+    }
+}
+
+fn threshold_default() -> f32 { 0.5 }
+fn tap_time_default() -> f32 { 0.25 }
+fn count_default() -> usize { 2 }
+
+// tracking info for a single interaction
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct InteractionState {
+    pressed_at: Option<f32>,
+    hold_fired: bool,
+    taps: usize,
+    last_tap: f32
+}
+
+// events
+#[derive(Event, Debug, Clone)]
+pub struct InputThresholdPressedEvent { pub entity: Entity, pub name: String, pub value: f32 }
+#[derive(Event, Debug, Clone)]
+pub struct InputReleasedEvent { pub entity: Entity, pub name: String, pub held: f32 }
+#[derive(Event, Debug, Clone)]
+pub struct InputTapEvent { pub entity: Entity, pub name: String }
+#[derive(Event, Debug, Clone)]
+pub struct InputHoldEvent { pub entity: Entity, pub name: String, pub duration: f32 }
+#[derive(Event, Debug, Clone)]
+pub struct InputMultiTapEvent { pub entity: Entity, pub name: String, pub count: usize }
+
+fn update_interactions(
+    mut inputs: Query<(Entity, &mut Inputs)>,
+    descriptions: Res<Assets<InputDescription>>,
+    time: Res<Time>,
+    mut press_events: EventWriter<InputThresholdPressedEvent>,
+    mut release_events: EventWriter<InputReleasedEvent>,
+    mut tap_events: EventWriter<InputTapEvent>,
+    mut hold_events: EventWriter<InputHoldEvent>,
+    mut multi_tap_events: EventWriter<InputMultiTapEvent>
+) {
+    let now = time.elapsed_seconds();
+    inputs.for_each_mut(|(entity, mut inputs)| {
+        // get the interactions of each binding of all contexts, higher contexts override actions with the same name
+        let mut interactions: HashMap<String, Vec<(usize, InputInteraction)>> = HashMap::new();
+        inputs.layers(&descriptions).iter().for_each(|description| {
+            description.elements.iter().for_each(|(name, input_types)| {
+                if interactions.contains_key(name) { return }
+                let list = input_types.iter().enumerate()
+                    .flat_map(|(index, input_type)| input_type.interactions().iter().map(move |interaction| (index, *interaction)))
+                    .collect();
+                interactions.insert(name.clone(), list);
+            });
+        });
+
+        interactions.iter().filter(|(_, interactions)| !interactions.is_empty()).for_each(|(name, interactions)| {
+            let bindings = inputs.binding_values.get(name).cloned().unwrap_or_default();
+            let states = inputs.interaction_states.entry(name.clone()).or_default();
+            states.resize(interactions.len(), InteractionState::default());
+
+            interactions.iter().zip(states.iter_mut()).for_each(|((index, interaction), state)| {
+                // each interaction only reads the binding it was added to
+                let value = bindings.get(*index).copied().unwrap_or(0.);
+                let is_pressed = value >= interaction.threshold();
+
+                // just pressed
+                if is_pressed && state.pressed_at.is_none() {
+                    state.pressed_at = Some(now);
+                    state.hold_fired = false;
+                    if let InputInteraction::Press { .. } = interaction {
+                        press_events.send(InputThresholdPressedEvent { entity, name: name.clone(), value });
+                    }
+                }
+
+                // held
+                if is_pressed {
+                    let held = now - state.pressed_at.unwrap();
+                    if let InputInteraction::Hold { duration, .. } = interaction {
+                        if !state.hold_fired && held >= *duration {
+                            state.hold_fired = true;
+                            hold_events.send(InputHoldEvent { entity, name: name.clone(), duration: held });
+                        }
+                    }
+                }
+
+                // just released
+                if !is_pressed && state.pressed_at.is_some() {
+                    let held = now - state.pressed_at.take().unwrap();
+                    match interaction {
+                        InputInteraction::Release { .. } => release_events.send(InputReleasedEvent { entity, name: name.clone(), held }),
+                        InputInteraction::Tap { max_time, .. } => if held <= *max_time {
+                            tap_events.send(InputTapEvent { entity, name: name.clone() });
+                        },
+                        InputInteraction::MultiTap { count, window, .. } => if held <= *window {
+                            // continue the tap chain if within the window, otherwise start a new one
+                            state.taps = if state.taps > 0 && now - state.last_tap <= *window + held { state.taps + 1 } else { 1 };
+                            state.last_tap = now;
+                            if state.taps >= *count {
+                                state.taps = 0;
+                                multi_tap_events.send(InputMultiTapEvent { entity, name: name.clone(), count: *count });
+                            }
+                        } else { state.taps = 0; },
+                        _ => {}
+                    }
+                }
+            });
+        });
+    });
+}
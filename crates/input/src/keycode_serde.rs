@@ -172,6 +172,6 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<KeyCode, D::Error> where D
         "Copy" => Ok(KeyCode::Copy),
         "Paste" => Ok(KeyCode::Paste),
         "Cut" => Ok(KeyCode::Cut),
-        _ => { error!("No keycode registered with value {value}"); Ok(KeyCode::Escape) }
+        _ => Err(de::Error::custom(format!("unknown key code \"{value}\"")))
     }
 }
\ No newline at end of file
@@ -1,7 +1,30 @@
-use bevy::{prelude::*, asset::{AssetLoader, AsyncReadExt}};
+use bevy::{prelude::*, asset::{AssetLoader, AsyncReadExt}, ecs::system::SystemParam};
+use commands::InputCommands;
+use contexts::{InputContextsPlugin, InputClaims, InputConsume, ConsumedInputs};
+use devices::{InputDevicesPlugin, assign_gamepads};
+use interactions::InputInteractionsPlugin;
+use mouse::{InputMousePlugin, AccumulatedMouse};
+use prompts::InputPromptsPlugin;
+use rebind::InputRebindPlugin;
+use recording::InputRecordingPlugin;
 use structs::*;
+use touch::{InputTouchPlugin, TouchControls};
+use validation::{parse_input_description, input_description_warnings};
 
 pub mod structs;
+pub mod actions;
+pub mod buffer;
+pub mod commands;
+pub mod contexts;
+pub mod devices;
+pub mod interactions;
+pub mod mouse;
+pub mod prompts;
+pub mod rebind;
+pub mod recording;
+pub mod testing;
+pub mod touch;
+pub mod validation;
 mod keycode_serde;
 mod mouse_button_serde;
 mod gamepad_axis_serde;
@@ -11,12 +34,13 @@ pub struct ConfigurableInputPlugin;
 impl Plugin for ConfigurableInputPlugin {
     fn build(&self, app: &mut App) {
         app
+            .add_plugins((InputCommands, InputDevicesPlugin, InputMousePlugin, InputRebindPlugin, InputInteractionsPlugin, InputContextsPlugin, InputRecordingPlugin, InputPromptsPlugin, InputTouchPlugin))
             .add_event::<InputPressedEvent>()
             .add_event::<InputDepressedEvent>()
             .add_event::<InputChangedEvent>()
             .init_asset::<InputDescription>()
             .init_asset_loader::<InputLoader>()
-            .add_systems(Update, update_inputs);
+            .add_systems(Update, update_inputs.after(assign_gamepads));
     }
 }
 
@@ -45,7 +69,7 @@ impl AssetLoader for InputLoader {
         &'a self,
         reader: &'a mut bevy::asset::io::Reader,
         _settings: &'a Self::Settings,
-        _load_context: &'a mut bevy::asset::LoadContext,
+        load_context: &'a mut bevy::asset::LoadContext,
     ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             // load content
@@ -56,110 +80,240 @@ impl AssetLoader for InputLoader {
             if content.is_err() { error!("Failed to load input json!"); return Err(InputLoadError("Failed to load json".into())) }
             let content = content.unwrap();
             
-            // load description
-            let description: Result<InputDescription, serde_json::Error> = serde_json::from_str(content);
-            if description.is_err() { error!("Failed to load input description from json"); return Err(InputLoadError("Failed to load description".into())) }
-            
-            // load final input map
-            // load_context.set_default_asset(LoadedAsset::new(description.unwrap()));
+            // load description, failing with the action and position of any mistake
+            let description = parse_input_description(content);
+            if description.is_err() {
+                let error = description.err().unwrap();
+                error!("Failed to load input description {:?}: {error}", load_context.path());
+                return Err(InputLoadError(error.to_string()))
+            }
+            let description = description.unwrap();
+
+            // warn about duplicate and conflicting bindings
+            input_description_warnings(content, &description).iter().for_each(|warning| warn!("Input description {:?}: {warning}", load_context.path()));
             
-            Ok(description.unwrap())
+            Ok(description)
         })
     }
 }
 
+// all device resources that inputs are read from
+#[derive(SystemParam)]
+pub struct InputSources<'w> {
+    pub keycodes: Res<'w, Input<KeyCode>>,
+    pub mouse_buttons: Res<'w, Input<MouseButton>>,
+    pub mouse: Res<'w, AccumulatedMouse>,
+    pub gamepads: Res<'w, Gamepads>,
+    pub gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    pub gamepad_button_axis: Res<'w, Axis<GamepadButton>>,
+    pub gamepad_axis: Res<'w, Axis<GamepadAxis>>,
+    pub touch: Res<'w, TouchControls>
+}
+
 // System that loads all active input maps
-fn update_inputs(
+pub(crate) fn update_inputs(
     // general
-    mut inputs: Query<&mut Inputs>,
+    mut inputs: Query<(Entity, &mut Inputs)>,
     descriptions: Res<Assets<InputDescription>>,
+    claims: Res<InputClaims>,
+    time: Res<Time>,
 
     // inputs
-    keycodes: Res<Input<KeyCode>>,
-    mouse_buttons: Res<Input<MouseButton>>,
-    gamepad_buttons: Res<Input<GamepadButton>>,
-    gamepad_axis: Res<Axis<GamepadAxis>>,
+    sources: InputSources,
 
     // events
     mut pressed_events: EventWriter<InputPressedEvent>,
     mut depressed_events: EventWriter<InputDepressedEvent>,
     mut changed_events: EventWriter<InputChangedEvent>
 ) {
-    inputs.for_each_mut(|mut inputs| {
+    inputs.for_each_mut(|(entity, mut inputs)| {
         let copy = inputs.clone(); // yes I know this is horribly inefficient
-        let description = match &copy.description {
-            InputDescriptionContainer::Raw(desc) => desc,
-            InputDescriptionContainer::Handle(handle) => {
-                // make sure description handle exists
-                if !descriptions.contains(handle) { return }
-
-                // load description
-                let description = descriptions.get(handle);
-                if description.is_none() { return }
-                description.unwrap()
-            },
-        };
-
-        // for each description element and read and save its input
-        description.elements.iter().for_each(|(name, input_types)| {
-            // get sum with a max of 1 of all inputs
-            let value: f32 = input_types.iter().map(|input_type| {
-                match input_type {
-                    InputType::SCALAR { element } => 
-                        input_element_to_f32(element, &keycodes, &mouse_buttons, &gamepad_buttons, &gamepad_axis),
-                    InputType::AXIS { positive, negative } => 
-                        input_element_to_f32(positive, &keycodes, &mouse_buttons, &gamepad_buttons, &gamepad_axis) - 
-                            input_element_to_f32(negative, &keycodes, &mouse_buttons, &gamepad_buttons, &gamepad_axis),
-                }
-            }).sum();
-            let value = value.min(1.);
+
+        // make sure base description is loaded
+        if copy.get_description(&descriptions).is_none() { return }
+        let now = time.elapsed_seconds();
+        inputs.prune_history(now);
+        let device = &copy.device;
+
+        // read each context from the top down, hiding what each context consumes from the ones below it
+        let mut consumed = ConsumedInputs::from_claims(&claims);
+        let mut results: Vec<(String, f32, Option<Vec2>, Vec<f32>)> = Vec::new();
+        let layers = copy.contexts.iter().map(|context| (&context.description, context.consume))
+            .chain(std::iter::once((&copy.description, InputConsume::None)));
+        layers.for_each(|(container, consume)| {
+            let description = container.get(&descriptions);
+            let description = if description.is_some() { description.unwrap() } else { return };
+
+            // higher contexts override actions with the same name
+            read_description(description, device, &sources, &consumed).into_iter().for_each(|result| {
+                if !results.iter().any(|(name, ..)| *name == result.0) { results.push(result); }
+            });
+            consumed.add(&consume, Some(description));
+        });
+
+        // actions of removed contexts go back to 0
+        let removed: Vec<(String, f32, Option<Vec2>, Vec<f32>)> = copy.values.keys()
+            .filter(|name| !results.iter().any(|(other, ..)| other == *name))
+            .map(|name| (name.clone(), 0., if copy.vectors.contains_key(name) { Some(Vec2::ZERO) } else { None }, Vec::new()))
+            .collect();
+        results.extend(removed);
+
+        results.into_iter().for_each(|(name, value, vector, bindings)| {
+            if vector.is_some() { inputs.set_vec2(name.clone(), vector.unwrap()); }
+            inputs.binding_values.insert(name.clone(), bindings);
 
             // get last value
             let old_value = inputs.get(&name);
             
             // pressed events
             if old_value < 1. && value >= 1. {
-                pressed_events.send(InputPressedEvent { name: name.clone(), value });
+                pressed_events.send(InputPressedEvent { entity, name: name.clone(), value });
+                inputs.record_transition(&name, true, now);
             }
 
             // depressed events
             if old_value >= 1. && value < 1. {
-                depressed_events.send(InputDepressedEvent { name: name.clone(), value });
+                depressed_events.send(InputDepressedEvent { entity, name: name.clone(), value });
+                inputs.record_transition(&name, false, now);
             }
 
             // change events
             if old_value != value {
-                changed_events.send(InputChangedEvent { name: name.clone(), value });
+                changed_events.send(InputChangedEvent { entity, name: name.clone(), value });
             }
 
             // update values map with the resulting value
-            inputs.set(name.clone(), value);
+            inputs.set(name, value);
         });
     })
 }
 
-// A function that converts a input element to a f32
+// A function that reads the value of every action in a description, its vector if it has VEC2 inputs and the value of each of its bindings
+fn read_description(description: &InputDescription, device: &InputDevice, sources: &InputSources, consumed: &ConsumedInputs) -> Vec<(String, f32, Option<Vec2>, Vec<f32>)> {
+    // find all held chords so less specific bindings can be suppressed
+    let active_chords: Vec<&Vec<InputElement>> = description.elements.values().flatten()
+        .filter_map(|input_type| match input_type {
+            InputType::CHORD { elements, .. } if chord_to_f32(elements, device, sources, consumed) > 0. => Some(elements),
+            _ => None
+        })
+        .collect();
+
+    // for each description element and read its input
+    description.elements.iter().map(|(name, input_types)| {
+        // sum all 2D inputs, keeping the vector length at most 1
+        let is_vector = input_types.iter().any(|input_type| matches!(input_type, InputType::VEC2 { .. }));
+        let vector: Vec2 = input_types.iter()
+            .filter(|input_type| matches!(input_type, InputType::VEC2 { .. }))
+            .map(|input_type| input_type_to_vec2(input_type, device, sources, consumed, &active_chords))
+            .sum::<Vec2>()
+            .clamp_length_max(1.);
+
+        // get sum of all inputs, clamped from -1 to 1 unless an input can go past that (like mouse motion)
+        let value: f32 = input_types.iter()
+            .filter(|input_type| !matches!(input_type, InputType::VEC2 { .. }))
+            .map(|input_type| input_type_to_f32(input_type, device, sources, consumed, &active_chords))
+            .sum::<f32>() + vector.length();
+        let value = if input_types.iter().all(|input_type| input_type.is_bounded()) { value.clamp(-1., 1.) } else { value };
+
+        // bindings are read on their own for their interactions
+        let bindings = input_types.iter().map(|input_type| input_type_to_f32(input_type, device, sources, consumed, &active_chords)).collect();
+        (name.clone(), value, if is_vector { Some(vector) } else { None }, bindings)
+    }).collect()
+}
+
+// A function that converts a non 2D input type to a f32, applying chord suppression
+fn input_type_to_f32(input_type: &InputType, device: &InputDevice, sources: &InputSources, consumed: &ConsumedInputs, active_chords: &[&Vec<InputElement>]) -> f32 {
+    match input_type {
+        InputType::SCALAR { element, .. } => 
+            if is_suppressed(&[element], active_chords) { 0.0 } 
+            else { input_element_to_f32(element, device, sources, consumed) },
+        InputType::AXIS { positive, negative, .. } => {
+            let positive = if is_suppressed(&[positive], active_chords) { 0.0 } else { input_element_to_f32(positive, device, sources, consumed) };
+            let negative = if is_suppressed(&[negative], active_chords) { 0.0 } else { input_element_to_f32(negative, device, sources, consumed) };
+            positive - negative
+        },
+        InputType::CHORD { elements, .. } =>
+            if is_suppressed(&elements.iter().collect::<Vec<_>>(), active_chords) { 0.0 }
+            else { chord_to_f32(elements, device, sources, consumed) },
+        InputType::VEC2 { .. } => input_type_to_vec2(input_type, device, sources, consumed, active_chords).length()
+    }
+}
+
+// A function that converts a 2D input type to a vector with a max length of 1 and its radial dead zone applied
+fn input_type_to_vec2(input_type: &InputType, device: &InputDevice, sources: &InputSources, consumed: &ConsumedInputs, active_chords: &[&Vec<InputElement>]) -> Vec2 {
+    match input_type {
+        InputType::VEC2 { x, y, dead_zone, .. } => {
+            let vector = Vec2::new(
+                input_type_to_f32(x, device, sources, consumed, active_chords), 
+                input_type_to_f32(y, device, sources, consumed, active_chords)
+            ).clamp_length_max(1.);
+            let length = apply_dead_zone(vector.length(), *dead_zone);
+            vector.normalize_or_zero() * length
+        },
+        _ => Vec2::ZERO
+    }
+}
+
+// A chord is only as pressed as its least pressed element
+fn chord_to_f32(elements: &[InputElement], device: &InputDevice, sources: &InputSources, consumed: &ConsumedInputs) -> f32 {
+    if elements.is_empty() { return 0.0 }
+    elements.iter().map(|element| input_element_to_f32(element, device, sources, consumed)).fold(f32::MAX, f32::min)
+}
+
+// A binding is suppressed if a held chord with more elements contains all of its elements (so S does not fire on Ctrl+S)
+fn is_suppressed(elements: &[&InputElement], active_chords: &[&Vec<InputElement>]) -> bool {
+    active_chords.iter().any(|chord| {
+        chord.len() > elements.len() && elements.iter().all(|element| chord.iter().any(|other| other.same_source(element)))
+    })
+}
+
+// A function that converts a input element to a f32 for the given device, 0 if consumed by a higher context
 fn input_element_to_f32(
     element: &InputElement,
-    keycodes: &Res<Input<KeyCode>>,
-    mouse_buttons: &Res<Input<MouseButton>>,
-    gamepad_buttons: &Res<Input<GamepadButton>>,
-    gamepad_axis: &Res<Axis<GamepadAxis>>
+    device: &InputDevice,
+    sources: &InputSources,
+    consumed: &ConsumedInputs
 ) -> f32 {
+    if consumed.blocks(element) { return 0.0 }
     match element {
-        InputElement::Keyboard { key } => if keycodes.pressed(*key) { 1.0 } else { 0.0 },
-        InputElement::Mouse { button } => if mouse_buttons.pressed(*button) { 1.0 } else { 0.0 },
+        InputElement::Keyboard { key } => 
+            if device.uses_keyboard_mouse() && sources.keycodes.pressed(*key) { 1.0 } else { 0.0 },
+        InputElement::Mouse { button } => 
+            if device.uses_keyboard_mouse() && sources.mouse_buttons.pressed(*button) { 1.0 } else { 0.0 },
+        InputElement::MouseMotion { axis, sensitivity } =>
+            if device.uses_keyboard_mouse() { axis.get(sources.mouse.motion) * *sensitivity } else { 0.0 },
+        InputElement::MouseWheel { axis, mult } =>
+            if device.uses_keyboard_mouse() { axis.get(sources.mouse.wheel) * *mult } else { 0.0 },
+        InputElement::CursorPosition { axis } =>
+            if device.uses_keyboard_mouse() { axis.get(sources.mouse.cursor) } else { 0.0 },
         InputElement::GamepadButton { button } => 
-            if gamepad_buttons.pressed(GamepadButton { gamepad: Gamepad { id: 0 }, button_type: *button }) { 1.0 } else { 0.0 },
+            if device.gamepads(&sources.gamepads).iter().any(|gamepad| sources.gamepad_buttons.pressed(GamepadButton { gamepad: *gamepad, button_type: *button })) { 1.0 } else { 0.0 },
+        InputElement::AnalogGamepadButton { button, threshold } =>
+            // use the most pulled value of all gamepads
+            (device.gamepads(&sources.gamepads).iter()
+                .map(|gamepad| sources.gamepad_button_axis.get(GamepadButton { gamepad: *gamepad, button_type: *button }).unwrap_or(0.))
+                .fold(0., f32::max) / threshold.max(f32::EPSILON)).clamp(0., 1.),
         InputElement::GamepadAxis { axis, mult } =>
-            gamepad_axis.get(GamepadAxis { gamepad: Gamepad { id: 0 }, axis_type: *axis }).unwrap_or(0.) * *mult
+            // use the axis value furthest from 0 of all gamepads
+            device.gamepads(&sources.gamepads).iter()
+                .map(|gamepad| sources.gamepad_axis.get(GamepadAxis { gamepad: *gamepad, axis_type: *axis }).unwrap_or(0.))
+                .fold(0., |a: f32, b: f32| if b.abs() > a.abs() { b } else { a }) * *mult,
+        InputElement::TouchButton { button } =>
+            if device.uses_keyboard_mouse() && sources.touch.button(button) { 1.0 } else { 0.0 },
+        InputElement::VirtualJoystick { joystick, axis } =>
+            if device.uses_keyboard_mouse() { axis.get(sources.touch.joystick(joystick)) } else { 0.0 },
+        InputElement::Modified { element, dead_zone, curve, sensitivity, invert } => {
+            let value = apply_dead_zone(input_element_to_f32(element, device, sources, consumed), *dead_zone);
+            let value = curve.apply(value) * *sensitivity;
+            if *invert { -value } else { value }
+        }
     }
 }
 
-// events
+// events, entity is the inputs component that sent it
 #[derive(Event)]
-pub struct InputPressedEvent { pub name: String, pub value: f32 }
+pub struct InputPressedEvent { pub entity: Entity, pub name: String, pub value: f32 }
 #[derive(Event)]
-pub struct InputDepressedEvent { pub name: String, pub value: f32 }
+pub struct InputDepressedEvent { pub entity: Entity, pub name: String, pub value: f32 }
 #[derive(Event)]
-pub struct InputChangedEvent { pub name: String, pub value: f32 }
\ No newline at end of file
+pub struct InputChangedEvent { pub entity: Entity, pub name: String, pub value: f32 }
\ No newline at end of file
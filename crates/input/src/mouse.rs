@@ -0,0 +1,73 @@
+use bevy::{prelude::*, input::mouse::{MouseMotion, MouseWheel, MouseScrollUnit}, window::PrimaryWindow};
+use serde::*;
+
+// plugin that accumulates mouse movement so it can be read as input elements
+pub struct InputMousePlugin;
+impl Plugin for InputMousePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AccumulatedMouse>()
+            .add_systems(PreUpdate, accumulate_mouse.after(bevy::input::InputSystem));
+    }
+}
+
+// an axis of mouse movement, scrolling or position
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseAxis {
+    #[default]
+    X,
+    Y
+}
+
+impl MouseAxis {
+    pub fn get(&self, vec: Vec2) -> f32 {
+        match self {
+            MouseAxis::X => vec.x,
+            MouseAxis::Y => vec.y
+        }
+    }
+}
+
+// mouse state for the current frame
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct AccumulatedMouse {
+    // movement in pixels this frame
+    pub motion: Vec2,
+    // scroll in lines this frame
+    pub wheel: Vec2,
+    // cursor position from -1 to 1 in the primary window with y up, 0 if the cursor is outside the window
+    pub cursor: Vec2
+}
+
+// how many pixels count as one line when scrolling with pixel units
+const PIXELS_PER_LINE: f32 = 20.;
+
+pub(crate) fn accumulate_mouse(
+    mut mouse: ResMut<AccumulatedMouse>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    window: Query<&Window, With<PrimaryWindow>>
+) {
+    // sum motion
+    mouse.motion = motion.read().map(|event| event.delta).sum();
+
+    // sum scroll
+    mouse.wheel = wheel.read().map(|event| {
+        match event.unit {
+            MouseScrollUnit::Line => Vec2::new(event.x, event.y),
+            MouseScrollUnit::Pixel => Vec2::new(event.x, event.y) / PIXELS_PER_LINE
+        }
+    }).sum();
+
+    // normalize cursor position
+    let window = window.get_single();
+    let window = if window.is_ok() { window.unwrap() } else { mouse.cursor = Vec2::ZERO; return };
+    let size = Vec2::new(window.width(), window.height());
+    mouse.cursor = match window.cursor_position() {
+        Some(position) if size.x > 0. && size.y > 0. => {
+            let normalized = position / size * 2. - Vec2::ONE;
+            Vec2::new(normalized.x, -normalized.y)
+        },
+        _ => Vec2::ZERO
+    };
+}
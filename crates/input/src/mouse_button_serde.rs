@@ -1,8 +1,12 @@
 use bevy::{prelude::*, reflect::Enum};
 use serde::*;
 
+// other buttons keep their index, like "Other(4)"
 pub fn serialize<S>(button: &MouseButton, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-    serializer.serialize_str(button.variant_name())
+    match button {
+        MouseButton::Other(index) => serializer.serialize_str(&format!("Other({index})")),
+        _ => serializer.serialize_str(button.variant_name())
+    }
 }
 
 pub fn deserialize<'de, D>(deserializer: D) -> Result<MouseButton, D::Error> where D: Deserializer<'de> {
@@ -12,6 +16,8 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<MouseButton, D::Error> whe
         "Left" => Ok(MouseButton::Left),
         "Right" => Ok(MouseButton::Right),
         "Middle" => Ok(MouseButton::Middle),
-        _ => { error!("No mouse button registered with value {value}"); Ok(MouseButton::Left) }
+        _ if value.starts_with("Other(") && value.ends_with(')') => value[6 .. value.len() - 1].parse().map(MouseButton::Other)
+            .map_err(|_| de::Error::custom(format!("invalid mouse button index in \"{value}\""))),
+        _ => Err(de::Error::custom(format!("unknown mouse button \"{value}\"")))
     }
 }
\ No newline at end of file
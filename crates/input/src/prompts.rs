@@ -0,0 +1,204 @@
+use bevy::{prelude::*, reflect::Enum};
+
+use crate::{structs::*, InputSources, update_inputs};
+
+// plugin that tracks the last used device so prompts can show the right bindings
+pub struct InputPromptsPlugin;
+impl Plugin for InputPromptsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<LastUsedDevice>()
+            .add_event::<InputDeviceChangedEvent>()
+            .add_systems(Update, track_last_device.before(update_inputs));
+    }
+}
+
+// A device that was actually used, unlike InputDevice which is what an inputs component may read from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UsedDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad(Gamepad)
+}
+
+impl UsedDevice {
+    pub fn is_gamepad(&self) -> bool { matches!(self, UsedDevice::Gamepad(_)) }
+}
+
+// resource with the last device used by anyone
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct LastUsedDevice(pub UsedDevice);
+
+// events
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputDeviceChangedEvent { pub entity: Entity, pub device: UsedDevice }
+
+// how far the mouse has to move or an axis has to be pushed to count as using the device
+const MOUSE_MOTION_THRESHOLD: f32 = 2.;
+const AXIS_THRESHOLD: f32 = 0.5;
+
+fn track_last_device(
+    mut inputs: Query<(Entity, &mut Inputs)>,
+    mut last: ResMut<LastUsedDevice>,
+    sources: InputSources,
+    mut events: EventWriter<InputDeviceChangedEvent>
+) {
+    // find all devices used this frame
+    let mut used = Vec::new();
+    if sources.keycodes.get_just_pressed().len() > 0 || sources.mouse_buttons.get_just_pressed().len() > 0 ||
+        sources.mouse.motion.length() > MOUSE_MOTION_THRESHOLD || sources.mouse.wheel != Vec2::ZERO {
+        used.push(UsedDevice::KeyboardMouse);
+    }
+    sources.gamepads.iter().for_each(|gamepad| {
+        let button = sources.gamepad_buttons.get_just_pressed().any(|button| button.gamepad == gamepad);
+        let axis = sources.gamepad_axis.devices().any(|axis| axis.gamepad == gamepad && sources.gamepad_axis.get(*axis).unwrap_or(0.).abs() > AXIS_THRESHOLD);
+        if button || axis { used.push(UsedDevice::Gamepad(gamepad)); }
+    });
+    if used.is_empty() { return }
+
+    // update global device, keeping the current one if it was used too
+    if !used.contains(&last.0) { last.0 = used[0]; }
+
+    // update each inputs with the devices it can read from
+    inputs.for_each_mut(|(entity, mut inputs)| {
+        if used.contains(&inputs.last_device) { return }
+        let gamepads = inputs.device.gamepads(&sources.gamepads);
+        let device = used.iter().find(|device| match device {
+            UsedDevice::KeyboardMouse => inputs.device.uses_keyboard_mouse(),
+            UsedDevice::Gamepad(gamepad) => gamepads.contains(gamepad)
+        });
+        if device.is_none() { return }
+        inputs.last_device = *device.unwrap();
+        events.send(InputDeviceChangedEvent { entity, device: inputs.last_device });
+    });
+}
+
+// A label and glyph ids for showing an actions bindings (ex. "Press [E]")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputPrompt {
+    pub device: UsedDevice,
+    // readable text like "E", "Ctrl+S" or "A"
+    pub label: String,
+    // ids of each element like "key_e" or "gamepad_south", for looking up images
+    pub glyphs: Vec<String>
+}
+
+impl std::fmt::Display for InputPrompt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.device.is_gamepad() { write!(f, "({})", self.label) } else { write!(f, "[{}]", self.label) }
+    }
+}
+
+impl InputDescription {
+    // get a prompt for the first binding of an action that the device can press, touch controls are skipped since they are labeled on screen
+    pub fn prompt(&self, name: &str, device: UsedDevice) -> Option<InputPrompt> {
+        let inputs = self.elements.get(name)?;
+        let input = inputs.iter().find(|input| input.elements().iter().all(|element| element_is_gamepad(element) == device.is_gamepad() && !element_is_touch(element)))?;
+        Some(InputPrompt { device, label: input_type_label(input), glyphs: input.elements().iter().map(|element| element_glyph(element)).collect() })
+    }
+}
+
+impl Inputs {
+    // get a prompt for an action using the device this inputs component last used, checking contexts first
+    pub fn prompt(&self, name: &str, descriptions: &Assets<InputDescription>) -> Option<InputPrompt> {
+        self.layers(descriptions).iter().find_map(|description| description.prompt(name, self.last_device))
+    }
+}
+
+fn element_is_gamepad(element: &InputElement) -> bool {
+    matches!(element.source(), InputElement::GamepadButton { .. } | InputElement::AnalogGamepadButton { .. } | InputElement::GamepadAxis { .. })
+}
+
+fn element_is_touch(element: &InputElement) -> bool {
+    matches!(element.source(), InputElement::TouchButton { .. } | InputElement::VirtualJoystick { .. })
+}
+
+fn input_type_label(input: &InputType) -> String {
+    match input {
+        InputType::SCALAR { element, .. } => element_label(element),
+        InputType::AXIS { positive, negative, .. } => format!("{}/{}", element_label(positive), element_label(negative)),
+        InputType::CHORD { elements, .. } => elements.iter().map(element_label).collect::<Vec<_>>().join("+"),
+        InputType::VEC2 { x, y, .. } => {
+            let (x, y) = (input_type_label(x), input_type_label(y));
+            if x == y { x } else { format!("{x} {y}") }
+        }
+    }
+}
+
+// get a short readable name for an element
+pub fn element_label(element: &InputElement) -> String {
+    match element.source() {
+        InputElement::Keyboard { key } => match key {
+            KeyCode::Return => "Enter".into(),
+            KeyCode::Escape => "Esc".into(),
+            KeyCode::Back => "Backspace".into(),
+            KeyCode::Grave => "`".into(),
+            KeyCode::ControlLeft | KeyCode::ControlRight => "Ctrl".into(),
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => "Shift".into(),
+            KeyCode::AltLeft | KeyCode::AltRight => "Alt".into(),
+            KeyCode::SuperLeft | KeyCode::SuperRight => "Super".into(),
+            _ => key.variant_name().trim_start_matches("Key").to_string()
+        },
+        InputElement::Mouse { button } => match button {
+            MouseButton::Left => "LMB".into(),
+            MouseButton::Right => "RMB".into(),
+            MouseButton::Middle => "MMB".into(),
+            MouseButton::Other(index) => format!("Mouse {index}")
+        },
+        InputElement::MouseMotion { .. } => "Mouse".into(),
+        InputElement::MouseWheel { .. } => "Scroll".into(),
+        InputElement::CursorPosition { .. } => "Cursor".into(),
+        InputElement::GamepadButton { button } | InputElement::AnalogGamepadButton { button, .. } => match button {
+            GamepadButtonType::South => "A".into(),
+            GamepadButtonType::East => "B".into(),
+            GamepadButtonType::West => "X".into(),
+            GamepadButtonType::North => "Y".into(),
+            GamepadButtonType::LeftTrigger => "LB".into(),
+            GamepadButtonType::RightTrigger => "RB".into(),
+            GamepadButtonType::LeftTrigger2 => "LT".into(),
+            GamepadButtonType::RightTrigger2 => "RT".into(),
+            GamepadButtonType::LeftThumb => "LS".into(),
+            GamepadButtonType::RightThumb => "RS".into(),
+            GamepadButtonType::DPadUp => "D-Pad Up".into(),
+            GamepadButtonType::DPadDown => "D-Pad Down".into(),
+            GamepadButtonType::DPadLeft => "D-Pad Left".into(),
+            GamepadButtonType::DPadRight => "D-Pad Right".into(),
+            _ => button.variant_name().to_string()
+        },
+        InputElement::GamepadAxis { axis, .. } => match axis {
+            GamepadAxisType::LeftStickX | GamepadAxisType::LeftStickY => "Left Stick".into(),
+            GamepadAxisType::RightStickX | GamepadAxisType::RightStickY => "Right Stick".into(),
+            GamepadAxisType::LeftZ => "LT".into(),
+            GamepadAxisType::RightZ => "RT".into(),
+            GamepadAxisType::Other(index) => format!("Axis {index}")
+        },
+        InputElement::TouchButton { button } => button.clone(),
+        InputElement::VirtualJoystick { joystick, .. } => joystick.clone(),
+        InputElement::Modified { .. } => unreachable!()
+    }
+}
+
+// get an image id for an element (ex. "key_e", "mouse_left", "gamepad_south", "gamepad_left_stick")
+pub fn element_glyph(element: &InputElement) -> String {
+    match element.source() {
+        InputElement::Keyboard { key } => format!("key_{}", snake_case(key.variant_name().trim_start_matches("Key"))),
+        InputElement::Mouse { button } => format!("mouse_{}", snake_case(button.variant_name())),
+        InputElement::MouseMotion { .. } => "mouse_move".into(),
+        InputElement::MouseWheel { .. } => "mouse_wheel".into(),
+        InputElement::CursorPosition { .. } => "mouse_cursor".into(),
+        InputElement::GamepadButton { button } | InputElement::AnalogGamepadButton { button, .. } => format!("gamepad_{}", snake_case(button.variant_name())),
+        InputElement::GamepadAxis { axis, .. } => format!("gamepad_{}", snake_case(axis.variant_name().trim_end_matches(['X', 'Y']))),
+        InputElement::TouchButton { button } => format!("touch_{button}"),
+        InputElement::VirtualJoystick { joystick, .. } => format!("touch_{joystick}"),
+        InputElement::Modified { .. } => unreachable!()
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    name.chars().enumerate().for_each(|(index, c)| {
+        if c.is_uppercase() && index > 0 { snake.push('_'); }
+        snake.push(c.to_ascii_lowercase());
+    });
+    snake
+}
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::{structs::*, InputSources};
+
+// plugin that handles inputs that are capturing a new binding
+pub struct InputRebindPlugin;
+impl Plugin for InputRebindPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<InputCapturedEvent>()
+            .add_event::<InputCaptureCancelledEvent>()
+            .add_systems(Update, capture_inputs);
+    }
+}
+
+// events
+#[derive(Event, Debug, Clone)]
+pub struct InputCapturedEvent {
+    pub entity: Entity,
+    pub action: String,
+    pub element: InputElement,
+    // other actions that already use the captured element
+    pub conflicts: Vec<String>
+}
+#[derive(Event, Debug, Clone)]
+pub struct InputCaptureCancelledEvent { pub entity: Entity, pub action: String }
+
+const CAPTURE_AXES: [GamepadAxisType; 6] = [
+    GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY, GamepadAxisType::LeftZ,
+    GamepadAxisType::RightStickX, GamepadAxisType::RightStickY, GamepadAxisType::RightZ
+];
+
+fn capture_inputs(
+    mut inputs: Query<(Entity, &mut Inputs)>,
+    mut descriptions: ResMut<Assets<InputDescription>>,
+    sources: InputSources,
+    mut captured_events: EventWriter<InputCapturedEvent>,
+    mut cancelled_events: EventWriter<InputCaptureCancelledEvent>
+) {
+    inputs.for_each_mut(|(entity, mut inputs)| {
+        let device = inputs.device;
+        let capture = inputs.capture.as_mut();
+        let capture = if capture.is_some() { capture.unwrap() } else { return };
+
+        // skip the frame the capture started so the input that started it is not captured
+        if !capture.started { capture.started = true; return }
+
+        // cancel if the cancel key was pressed
+        if capture.cancel_key.is_some() && sources.keycodes.just_pressed(capture.cancel_key.unwrap()) {
+            cancelled_events.send(InputCaptureCancelledEvent { entity, action: capture.action.clone() });
+            inputs.capture = None;
+            return
+        }
+
+        // look for the next element
+        let element = next_element(&device, capture.axis_threshold, &sources);
+        let element = if element.is_some() { element.unwrap() } else { return };
+        let capture = inputs.capture.take().unwrap();
+
+        // get description to modify
+        let description = match &mut inputs.description {
+            InputDescriptionContainer::Raw(description) => Some(description),
+            InputDescriptionContainer::Handle(handle) => descriptions.get_mut(handle.id())
+        };
+        let description = if description.is_some() { description.unwrap() } else { error!("Input description not loaded, could not bind {}!", capture.action); return };
+
+        // bind element and report conflicts
+        let conflicts = description.conflicts(&capture.action, &element);
+        if !conflicts.is_empty() { warn!("Binding {:?} to {} conflicts with {:?}", element, capture.action, conflicts); }
+        description.bind(capture.action.clone(), element.clone(), capture.mode);
+        captured_events.send(InputCapturedEvent { entity, action: capture.action, element, conflicts });
+    });
+}
+
+// get the next pressed key, button or moved axis from the given device
+fn next_element(device: &InputDevice, axis_threshold: f32, sources: &InputSources) -> Option<InputElement> {
+    if device.uses_keyboard_mouse() {
+        let key = sources.keycodes.get_just_pressed().next();
+        if key.is_some() { return Some(InputElement::Keyboard { key: *key.unwrap() }) }
+
+        let button = sources.mouse_buttons.get_just_pressed().next();
+        if button.is_some() { return Some(InputElement::Mouse { button: *button.unwrap() }) }
+    }
+
+    let gamepads = device.gamepads(&sources.gamepads);
+    let button = sources.gamepad_buttons.get_just_pressed().find(|button| gamepads.contains(&button.gamepad));
+    if button.is_some() { return Some(InputElement::GamepadButton { button: button.unwrap().button_type }) }
+
+    // axis moved past the threshold, mult matches the direction it was moved
+    for gamepad in gamepads.iter() {
+        for axis in CAPTURE_AXES.iter() {
+            let value = sources.gamepad_axis.get(GamepadAxis { gamepad: *gamepad, axis_type: *axis }).unwrap_or(0.);
+            if value.abs() >= axis_threshold { return Some(InputElement::GamepadAxis { axis: *axis, mult: value.signum() }) }
+        }
+    }
+
+    None
+}
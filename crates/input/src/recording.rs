@@ -0,0 +1,322 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap, time::TimeUpdateStrategy, input::gamepad::{GamepadConnectionEvent, GamepadConnection, GamepadInfo}};
+use serde::*;
+
+use crate::{structs::Inputs, mouse::{AccumulatedMouse, accumulate_mouse}, touch::{TouchControls, update_touch_controls}, update_inputs};
+
+// plugin that records device state and input values every frame, and can replay them in place of the real devices
+pub struct InputRecordingPlugin;
+impl Plugin for InputRecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<InputRecorder>()
+            .add_event::<InputPlaybackMismatchEvent>()
+            .add_event::<InputPlaybackFinishedEvent>()
+            .add_systems(PreUpdate, play_frame.after(accumulate_mouse).after(update_touch_controls))
+            .add_systems(Update, (record_frame, advance_playback).after(update_inputs));
+    }
+}
+
+// A recorded key, stored by name so recordings stay readable
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedKey(#[serde(with = "crate::keycode_serde")] pub KeyCode);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedMouseButton(#[serde(with = "crate::mouse_button_serde")] pub MouseButton);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedGamepadButton {
+    pub gamepad: usize,
+    #[serde(with = "crate::gamepad_button_serde")]
+    pub button: GamepadButtonType
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RecordedGamepadButtonValue {
+    pub gamepad: usize,
+    #[serde(with = "crate::gamepad_button_serde")]
+    pub button: GamepadButtonType,
+    pub value: f32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RecordedGamepadAxis {
+    pub gamepad: usize,
+    #[serde(with = "crate::gamepad_axis_serde")]
+    pub axis: GamepadAxisType,
+    pub value: f32
+}
+
+// All device state and resolved input values of a single frame
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct InputFrame {
+    // seconds since the last frame
+    pub delta: f32,
+    pub keys: Vec<RecordedKey>,
+    pub mouse_buttons: Vec<RecordedMouseButton>,
+    pub mouse: [Vec2; 3],
+    pub gamepads: Vec<usize>,
+    pub gamepad_buttons: Vec<RecordedGamepadButton>,
+    // how far analog buttons are pressed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gamepad_button_values: Vec<RecordedGamepadButtonValue>,
+    pub gamepad_axes: Vec<RecordedGamepadAxis>,
+    // on screen controls by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub touch_buttons: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub joysticks: HashMap<String, Vec2>,
+    // values of every inputs component with a name, by name then action
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub values: HashMap<String, HashMap<String, f32>>
+}
+
+// A list of recorded frames that can be saved to and loaded from a file
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct InputRecording {
+    pub frames: Vec<InputFrame>
+}
+
+impl InputRecording {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let json = self.to_json().map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json).map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+
+    // total length of the recording in seconds
+    pub fn duration(&self) -> f32 { self.frames.iter().map(|frame| frame.delta).sum() }
+}
+
+// A recording that is being played back
+#[derive(Debug, Clone)]
+pub struct InputPlayback {
+    pub recording: InputRecording,
+    pub frame: usize,
+    // start over when finished instead of stopping (ex. attract modes)
+    pub looping: bool,
+    // send mismatch events when input values differ from the recorded ones
+    pub verify: bool,
+    // step time by the recorded frame times so playback is deterministic
+    pub fixed_time: bool,
+    started: bool
+}
+
+// resource that controls recording and playback
+#[derive(Resource, Debug, Default, Clone)]
+pub struct InputRecorder {
+    pub recording: Option<InputRecording>,
+    pub playback: Option<InputPlayback>,
+    was_playing: bool,
+    controls_time: bool
+}
+
+impl InputRecorder {
+    pub fn start_recording(&mut self) { self.recording = Some(InputRecording::default()); }
+    pub fn stop_recording(&mut self) -> Option<InputRecording> { self.recording.take() }
+    pub fn is_recording(&self) -> bool { self.recording.is_some() }
+
+    // replay a recording in place of the real devices, verifying values and stepping time by the recorded frame times
+    pub fn play(&mut self, recording: InputRecording) {
+        self.playback = Some(InputPlayback { recording, frame: 0, looping: false, verify: true, fixed_time: true, started: false });
+    }
+
+    // replay a recording forever using real time
+    pub fn play_looping(&mut self, recording: InputRecording) {
+        self.playback = Some(InputPlayback { recording, frame: 0, looping: true, verify: false, fixed_time: false, started: false });
+    }
+
+    pub fn stop_playback(&mut self) { self.playback = None; }
+    pub fn is_playing(&self) -> bool { self.playback.is_some() }
+}
+
+// events
+#[derive(Event, Debug, Clone)]
+pub struct InputPlaybackMismatchEvent { pub frame: usize, pub inputs: String, pub name: String, pub expected: f32, pub actual: f32 }
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InputPlaybackFinishedEvent { pub frames: usize }
+
+// how far a replayed value can be from the recorded one before it counts as a mismatch
+const MISMATCH_TOLERANCE: f32 = 0.0001;
+
+fn record_frame(
+    mut recorder: ResMut<InputRecorder>,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mouse: Res<AccumulatedMouse>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_button_axis: Res<Axis<GamepadButton>>,
+    gamepad_axis: Res<Axis<GamepadAxis>>,
+    touch: Res<TouchControls>,
+    inputs: Query<(&Name, &Inputs)>
+) {
+    let recording = recorder.recording.as_mut();
+    let recording = if recording.is_some() { recording.unwrap() } else { return };
+
+    // save device state and values
+    recording.frames.push(InputFrame {
+        delta: time.delta_seconds(),
+        keys: keys.get_pressed().map(|key| RecordedKey(*key)).collect(),
+        mouse_buttons: mouse_buttons.get_pressed().map(|button| RecordedMouseButton(*button)).collect(),
+        mouse: [mouse.motion, mouse.wheel, mouse.cursor],
+        gamepads: gamepads.iter().map(|gamepad| gamepad.id).collect(),
+        gamepad_buttons: gamepad_buttons.get_pressed()
+            .map(|button| RecordedGamepadButton { gamepad: button.gamepad.id, button: button.button_type })
+            .collect(),
+        gamepad_button_values: gamepad_button_axis.devices()
+            .map(|button| RecordedGamepadButtonValue { gamepad: button.gamepad.id, button: button.button_type, value: gamepad_button_axis.get(*button).unwrap_or(0.) })
+            .filter(|button| button.value != 0.)
+            .collect(),
+        gamepad_axes: gamepad_axis.devices()
+            .map(|axis| RecordedGamepadAxis { gamepad: axis.gamepad.id, axis: axis.axis_type, value: gamepad_axis.get(*axis).unwrap_or(0.) })
+            .collect(),
+        touch_buttons: touch.buttons.iter().filter(|(_, button)| button.pressed).map(|(name, _)| name.clone()).collect(),
+        joysticks: touch.joysticks.iter().filter(|(_, joystick)| joystick.value != Vec2::ZERO).map(|(name, joystick)| (name.clone(), joystick.value)).collect(),
+        values: inputs.iter().map(|(name, inputs)| (name.to_string(), inputs.values.clone())).collect()
+    });
+}
+
+pub(crate) fn play_frame(
+    mut recorder: ResMut<InputRecorder>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse_buttons: ResMut<Input<MouseButton>>,
+    mut mouse: ResMut<AccumulatedMouse>,
+    gamepads: Res<Gamepads>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+    mut gamepad_button_axis: ResMut<Axis<GamepadButton>>,
+    mut gamepad_axis: ResMut<Axis<GamepadAxis>>,
+    mut touch: ResMut<TouchControls>,
+    mut connections: EventWriter<GamepadConnectionEvent>
+) {
+    // give devices and time back once playback stops
+    let playback = recorder.playback.as_mut();
+    let playback = if playback.is_some() { playback.unwrap() } else {
+        if recorder.was_playing {
+            recorder.was_playing = false;
+            keys.reset_all();
+            mouse_buttons.reset_all();
+            gamepad_buttons.reset_all();
+            *mouse = AccumulatedMouse::default();
+            touch.buttons.values_mut().for_each(|button| button.pressed = false);
+            touch.joysticks.values_mut().for_each(|joystick| joystick.value = Vec2::ZERO);
+        }
+        if recorder.controls_time { *time_strategy = TimeUpdateStrategy::Automatic; recorder.controls_time = false; }
+        return
+    };
+
+    // connect recorded gamepads first so they show up in the gamepads resource, playback starts next frame
+    if !playback.started {
+        playback.started = true;
+        let mut ids: Vec<usize> = playback.recording.frames.iter().flat_map(|frame| frame.gamepads.iter().copied()).collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter().filter(|id| !gamepads.contains(Gamepad::new(*id))).for_each(|id| {
+            connections.send(GamepadConnectionEvent::new(Gamepad::new(id), GamepadConnection::Connected(GamepadInfo { name: "Recorded Gamepad".into() })));
+        });
+        return
+    }
+    let frame = playback.recording.frames.get(playback.frame);
+    let frame = if frame.is_some() { frame.unwrap() } else { return };
+    let empty = InputFrame::default();
+    let previous = if playback.frame > 0 { &playback.recording.frames[playback.frame - 1] } else { &empty };
+
+    // step time by the next frames delta
+    let next = playback.recording.frames.get(playback.frame + 1);
+    let fixed_time = playback.fixed_time;
+    if fixed_time && next.is_some() {
+        *time_strategy = TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(next.unwrap().delta));
+    }
+
+    // replace buttons
+    replay_buttons(&mut keys, previous.keys.iter().map(|key| key.0), frame.keys.iter().map(|key| key.0));
+    replay_buttons(&mut mouse_buttons, previous.mouse_buttons.iter().map(|button| button.0), frame.mouse_buttons.iter().map(|button| button.0));
+    let to_gamepad_button = |button: &RecordedGamepadButton| GamepadButton { gamepad: Gamepad::new(button.gamepad), button_type: button.button };
+    replay_buttons(&mut gamepad_buttons, previous.gamepad_buttons.iter().map(to_gamepad_button), frame.gamepad_buttons.iter().map(to_gamepad_button));
+
+    // replace mouse and axes
+    mouse.motion = frame.mouse[0];
+    mouse.wheel = frame.mouse[1];
+    mouse.cursor = frame.mouse[2];
+    let axes: Vec<GamepadAxis> = gamepad_axis.devices().copied().collect();
+    axes.into_iter().for_each(|axis| { gamepad_axis.remove(axis); });
+    frame.gamepad_axes.iter().for_each(|axis| {
+        gamepad_axis.set(GamepadAxis { gamepad: Gamepad::new(axis.gamepad), axis_type: axis.axis }, axis.value);
+    });
+    let buttons: Vec<GamepadButton> = gamepad_button_axis.devices().copied().collect();
+    buttons.into_iter().for_each(|button| { gamepad_button_axis.remove(button); });
+    frame.gamepad_button_values.iter().for_each(|button| {
+        gamepad_button_axis.set(GamepadButton { gamepad: Gamepad::new(button.gamepad), button_type: button.button }, button.value);
+    });
+
+    // replace on screen controls
+    touch.buttons.iter_mut().for_each(|(name, button)| button.pressed = frame.touch_buttons.contains(name));
+    touch.joysticks.iter_mut().for_each(|(name, joystick)| joystick.value = frame.joysticks.get(name).copied().unwrap_or(Vec2::ZERO));
+    recorder.controls_time = fixed_time;
+    recorder.was_playing = true;
+}
+
+// set a buttons input to exactly the recorded state, keeping just pressed and just released correct
+fn replay_buttons<T: Copy + Eq + std::hash::Hash + Send + Sync + 'static>(input: &mut Input<T>, previous: impl Iterator<Item = T>, current: impl Iterator<Item = T>) {
+    let previous: Vec<T> = previous.collect();
+    let current: Vec<T> = current.collect();
+    input.reset_all();
+    previous.iter().for_each(|button| input.press(*button));
+    input.clear();
+    previous.iter().filter(|button| !current.contains(button)).for_each(|button| input.release(*button));
+    current.iter().for_each(|button| input.press(*button));
+}
+
+fn advance_playback(
+    mut recorder: ResMut<InputRecorder>,
+    inputs: Query<(&Name, &Inputs)>,
+    mut mismatch_events: EventWriter<InputPlaybackMismatchEvent>,
+    mut finished_events: EventWriter<InputPlaybackFinishedEvent>
+) {
+    let playback = recorder.playback.as_mut();
+    let playback = if playback.is_some() { playback.unwrap() } else { return };
+    if !playback.started { return }
+
+    // an empty recording finishes once instead of every frame when looping
+    if playback.recording.frames.is_empty() {
+        finished_events.send(InputPlaybackFinishedEvent { frames: 0 });
+        recorder.playback = None;
+        return
+    }
+
+    // compare values to the recorded ones
+    let frame = playback.recording.frames.get(playback.frame);
+    if playback.verify && frame.is_some() {
+        let frame = frame.unwrap();
+        inputs.iter().for_each(|(name, inputs)| {
+            let expected = frame.values.get(name.as_str());
+            let expected = if expected.is_some() { expected.unwrap() } else { return };
+            expected.iter().for_each(|(action, expected)| {
+                let actual = inputs.get(action);
+                if (actual - expected).abs() <= MISMATCH_TOLERANCE { return }
+                mismatch_events.send(InputPlaybackMismatchEvent { frame: playback.frame, inputs: name.to_string(), name: action.clone(), expected: *expected, actual });
+            });
+        });
+    }
+
+    // move to next frame, looping or stopping at the end
+    playback.frame += 1;
+    if playback.frame < playback.recording.frames.len() { return }
+    finished_events.send(InputPlaybackFinishedEvent { frames: playback.recording.frames.len() });
+    if playback.looping { playback.frame = 0; } else { recorder.playback = None; }
+}
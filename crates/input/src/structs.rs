@@ -1,9 +1,14 @@
+use std::collections::VecDeque;
+
 use bevy::{prelude::*, reflect::{TypeUuid, TypePath}, utils::HashMap};
 use serde::*;
 
+use crate::{mouse::MouseAxis, interactions::{InputInteraction, InteractionState}, contexts::InputContext, buffer::InputTransition, prompts::UsedDevice};
+
 // A serializable version of input map
 #[derive(Serialize, Deserialize, Component, TypeUuid, TypePath, Asset, Debug, Default, Clone)]
 #[uuid = "135601b6-2de3-4497-8f4b-3f4841948584"]
+#[serde(deny_unknown_fields)]
 pub struct InputDescription {
     pub elements: HashMap<String, Vec<InputType>>
 }
@@ -25,6 +30,15 @@ impl InputDescription {
         self
     }
 
+    // add an interaction like a hold or double tap to the binding at the given index of an action
+    pub fn interaction(&mut self, name: impl Into<String>, index: usize, interaction: InputInteraction) -> &mut Self {
+        let name = name.into();
+        let input = self.elements.get_mut(&name).and_then(|inputs| inputs.get_mut(index));
+        if input.is_some() { input.unwrap().interactions_mut().push(interaction); }
+        else { warn!("No binding at index {index} of action \"{name}\" to add an interaction to!"); }
+        self
+    }
+
     pub fn get(&mut self, name: impl Into<String>) -> Option<&Vec<InputType>> {
         self.elements.get(&name.into())
     }
@@ -32,6 +46,54 @@ impl InputDescription {
     pub fn get_mut(&mut self, name: impl Into<String>) -> Option<&mut Vec<InputType>> {
         self.elements.get_mut(&name.into())
     }
+
+    // bind an element to an action using the given capture mode
+    pub fn bind(&mut self, name: impl Into<String>, element: InputElement, mode: InputCaptureMode) -> &mut Self {
+        // replaced bindings keep their interactions so a rebound hold is still a hold
+        let inputs = self.elements.entry(name.into()).or_default();
+        match mode {
+            InputCaptureMode::Replace => {
+                let interactions = inputs.first().map(|input| input.interactions().to_vec()).unwrap_or_default();
+                *inputs = vec![InputType::SCALAR { element, interactions }];
+            },
+            InputCaptureMode::Append => inputs.push(InputType::scalar(element)),
+            InputCaptureMode::Set { index } => {
+                if index < inputs.len() { inputs[index] = InputType::SCALAR { element, interactions: inputs[index].interactions().to_vec() }; }
+                else { inputs.push(InputType::scalar(element)); }
+            },
+            InputCaptureMode::Positive { index } => match inputs.get_mut(index) {
+                Some(InputType::AXIS { positive, .. }) => *positive = element,
+                _ => warn!("No axis at index {index} to set positive element of!")
+            },
+            InputCaptureMode::Negative { index } => match inputs.get_mut(index) {
+                Some(InputType::AXIS { negative, .. }) => *negative = element,
+                _ => warn!("No axis at index {index} to set negative element of!")
+            }
+        }
+        self
+    }
+
+    // get all actions other than the given one that already use the given element, chords of multiple elements do not conflict
+    pub fn conflicts(&self, name: &str, element: &InputElement) -> Vec<String> {
+        let mut conflicts: Vec<String> = self.elements.iter()
+            .filter(|(other, inputs)| *other != name && inputs.iter().any(|input| {
+                input.uses(element) && !matches!(input, InputType::CHORD { elements, .. } if elements.len() > 1)
+            }))
+            .map(|(other, _)| other.clone())
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    // save this description as a .input file
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let json = self.to_json().map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        std::fs::write(path, json)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -40,22 +102,121 @@ pub enum InputDescriptionContainer {
     Handle(Handle<InputDescription>)
 }
 
+impl InputDescriptionContainer {
+    // get the description, either the raw one or the loaded asset
+    pub fn get<'a>(&'a self, descriptions: &'a Assets<InputDescription>) -> Option<&'a InputDescription> {
+        match self {
+            InputDescriptionContainer::Raw(description) => Some(description),
+            InputDescriptionContainer::Handle(handle) => descriptions.get(handle)
+        }
+    }
+}
+
+// The devices an inputs component reads from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputDevice {
+    // keyboard, mouse and all gamepads
+    #[default]
+    All,
+    KeyboardMouse,
+    AnyGamepad,
+    Gamepad(Gamepad),
+    None
+}
+
+impl InputDevice {
+    pub fn uses_keyboard_mouse(&self) -> bool { matches!(self, InputDevice::All | InputDevice::KeyboardMouse) }
+
+    // get all gamepads this device reads from
+    pub fn gamepads(&self, gamepads: &Gamepads) -> Vec<Gamepad> {
+        match self {
+            InputDevice::All | InputDevice::AnyGamepad => gamepads.iter().collect(),
+            InputDevice::Gamepad(gamepad) => vec![*gamepad],
+            _ => Vec::new()
+        }
+    }
+}
+
+// how many seconds of presses and releases inputs keep by default
+pub const DEFAULT_BUFFER_TIME: f32 = 0.5;
+
 #[derive(Component, Debug, Clone)]
 pub struct Inputs {
     pub description: InputDescriptionContainer,
-    pub values: HashMap<String, f32>
+    pub device: InputDevice,
+    // the device this inputs component was last used with, for prompts
+    pub last_device: UsedDevice,
+    pub values: HashMap<String, f32>,
+    pub vectors: HashMap<String, Vec2>,
+    pub capture: Option<InputCapture>,
+    pub contexts: Vec<InputContext>,
+    // seconds of presses and releases kept for buffering
+    pub buffer_time: f32,
+    pub(crate) history: HashMap<String, VecDeque<InputTransition>>,
+    pub(crate) now: f32,
+    // value of each binding of each action, read by interactions
+    pub(crate) binding_values: HashMap<String, Vec<f32>>,
+    pub(crate) interaction_states: HashMap<String, Vec<InteractionState>>
 }
 impl Inputs {
     pub fn from_handle(handle: Handle<InputDescription>) -> Self {
-        Self { description: InputDescriptionContainer::Handle(handle), values: HashMap::new() }
+        Self::with_container(InputDescriptionContainer::Handle(handle))
     }
 
     pub fn from_description(description: InputDescription) -> Self {
-        Self { description: InputDescriptionContainer::Raw(description), values: HashMap::new() }
+        Self::with_container(InputDescriptionContainer::Raw(description))
     }
 
     pub fn new<F>(f: F) -> Self where F: Fn(&mut InputDescription) {
-        Self { description: InputDescriptionContainer::Raw(InputDescription::create(f)), values: HashMap::new() }
+        Self::with_container(InputDescriptionContainer::Raw(InputDescription::create(f)))
+    }
+
+    fn with_container(description: InputDescriptionContainer) -> Self {
+        Self { description, device: InputDevice::default(), last_device: UsedDevice::default(), values: HashMap::new(), vectors: HashMap::new(), capture: None, contexts: Vec::new(), buffer_time: DEFAULT_BUFFER_TIME, history: HashMap::new(), now: 0., binding_values: HashMap::new(), interaction_states: HashMap::new() }
+    }
+
+    pub fn with_device(mut self, device: InputDevice) -> Self {
+        self.device = device;
+        self
+    }
+
+    pub fn set_device(&mut self, device: InputDevice) {
+        self.device = device;
+    }
+
+    // wait for the next key, button or axis movement and bind it to the given action
+    pub fn start_capture(&mut self, action: impl Into<String>, mode: InputCaptureMode) {
+        self.capture = Some(InputCapture { action: action.into(), mode, axis_threshold: 0.5, cancel_key: Some(KeyCode::Escape), started: false });
+    }
+
+    pub fn cancel_capture(&mut self) { self.capture = None; }
+    pub fn is_capturing(&self) -> bool { self.capture.is_some() }
+
+    // get the description, either the raw one or the loaded asset
+    pub fn get_description<'a>(&'a self, descriptions: &'a Assets<InputDescription>) -> Option<&'a InputDescription> {
+        self.description.get(descriptions)
+    }
+
+    // add a context on top of the base description and any contexts with the same or lower priority, replacing any context with the same name
+    pub fn push_context(&mut self, context: InputContext) {
+        self.contexts.retain(|other| other.name != context.name);
+        let index = self.contexts.iter().position(|other| other.priority <= context.priority).unwrap_or(self.contexts.len());
+        self.contexts.insert(index, context);
+    }
+
+    // remove a context by name, its actions go back to 0 on the next update
+    pub fn pop_context(&mut self, name: &str) -> Option<InputContext> {
+        let index = self.contexts.iter().position(|context| context.name == name);
+        if index.is_some() { Some(self.contexts.remove(index.unwrap())) } else { None }
+    }
+
+    pub fn has_context(&self, name: &str) -> bool { self.contexts.iter().any(|context| context.name == name) }
+
+    // get all loaded descriptions from the highest priority context down to the base description
+    pub fn layers<'a>(&'a self, descriptions: &'a Assets<InputDescription>) -> Vec<&'a InputDescription> {
+        self.contexts.iter().map(|context| &context.description).chain(std::iter::once(&self.description))
+            .filter_map(|container| container.get(descriptions))
+            .collect()
     }
 
     pub fn get(&self, name: &String) -> f32 {
@@ -65,19 +226,117 @@ impl Inputs {
     pub fn set(&mut self, name: String, value: f32) {
         self.values.insert(name, value);
     }
+
+    // get the value of a 2D action, zero if the action has no VEC2 inputs
+    pub fn get_vec2(&self, name: &String) -> Vec2 {
+        *self.vectors.get(name).unwrap_or(&Vec2::ZERO)
+    }
+
+    pub fn set_vec2(&mut self, name: String, value: Vec2) {
+        self.vectors.insert(name, value);
+    }
 }
 
-// Represents all possible input, types, scalar (0 -> 1, 1 input) or axis (-1 -> 1, 2 inputs)
-#[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(tag = "type")]
+// Represents all possible input, types, scalar (0 -> 1, 1 input), axis (-1 -> 1, 2 inputs), chord (0 -> 1, all inputs must be held)
+// or vec2 (two inputs combined into a vector with a max length of 1, readable with Inputs::get_vec2)
+// each binding can have its own interactions, which read the value of that binding only
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", deny_unknown_fields)]
 pub enum InputType {
-    SCALAR { element: InputElement },
-    AXIS { positive: InputElement, negative: InputElement }
+    SCALAR {
+        element: InputElement,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        interactions: Vec<InputInteraction>
+    },
+    AXIS {
+        positive: InputElement,
+        negative: InputElement,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        interactions: Vec<InputInteraction>
+    },
+    CHORD {
+        elements: Vec<InputElement>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        interactions: Vec<InputInteraction>
+    },
+    VEC2 {
+        x: Box<InputType>,
+        y: Box<InputType>,
+        // radial dead zone applied to the length of the vector
+        #[serde(default)]
+        dead_zone: f32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        interactions: Vec<InputInteraction>
+    }
+}
+
+impl InputType {
+    pub fn scalar(element: InputElement) -> Self { InputType::SCALAR { element, interactions: Vec::new() } }
+
+    pub fn axis(positive: InputElement, negative: InputElement) -> Self { InputType::AXIS { positive, negative, interactions: Vec::new() } }
+
+    pub fn chord(elements: Vec<InputElement>) -> Self { InputType::CHORD { elements, interactions: Vec::new() } }
+
+    // create a 2D input from two other inputs, like two stick axes or WASD as two AXIS inputs
+    pub fn vec2(x: InputType, y: InputType) -> Self { InputType::VEC2 { x: Box::new(x), y: Box::new(y), dead_zone: 0., interactions: Vec::new() } }
+
+    // add an interaction like a hold or double tap to this binding
+    pub fn with_interaction(mut self, interaction: InputInteraction) -> Self {
+        self.interactions_mut().push(interaction);
+        self
+    }
+
+    pub fn interactions(&self) -> &[InputInteraction] {
+        match self {
+            InputType::SCALAR { interactions, .. } |
+            InputType::AXIS { interactions, .. } |
+            InputType::CHORD { interactions, .. } |
+            InputType::VEC2 { interactions, .. } => interactions
+        }
+    }
+
+    pub fn interactions_mut(&mut self) -> &mut Vec<InputInteraction> {
+        match self {
+            InputType::SCALAR { interactions, .. } |
+            InputType::AXIS { interactions, .. } |
+            InputType::CHORD { interactions, .. } |
+            InputType::VEC2 { interactions, .. } => interactions
+        }
+    }
+
+    // get every element this input reads from
+    pub fn elements(&self) -> Vec<&InputElement> {
+        match self {
+            InputType::SCALAR { element, .. } => vec![element],
+            InputType::AXIS { positive, negative, .. } => vec![positive, negative],
+            InputType::CHORD { elements, .. } => elements.iter().collect(),
+            InputType::VEC2 { x, y, .. } => x.elements().into_iter().chain(y.elements()).collect()
+        }
+    }
+
+    pub fn uses(&self, element: &InputElement) -> bool {
+        match self {
+            InputType::SCALAR { element: a, .. } => a.same_source(element),
+            InputType::AXIS { positive, negative, .. } => positive.same_source(element) || negative.same_source(element),
+            InputType::CHORD { elements, .. } => elements.iter().any(|a| a.same_source(element)),
+            InputType::VEC2 { x, y, .. } => x.uses(element) || y.uses(element)
+        }
+    }
+
+    // check if the value of this input always stays between -1 and 1
+    pub fn is_bounded(&self) -> bool {
+        match self {
+            InputType::SCALAR { element, .. } => element.is_bounded(),
+            InputType::AXIS { positive, negative, .. } => positive.is_bounded() && negative.is_bounded(),
+            InputType::CHORD { elements, .. } => elements.iter().all(|element| element.is_bounded()),
+            InputType::VEC2 { .. } => true
+        }
+    }
 }
 
 // Represents an input element like a keyboard key or a mouse axis
-#[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(tag = "type")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", deny_unknown_fields)]
 pub enum InputElement {
     Keyboard {
         #[serde(with = "crate::keycode_serde")]
@@ -87,16 +346,189 @@ pub enum InputElement {
         #[serde(with = "crate::mouse_button_serde")]
         button: MouseButton
     },
+    // mouse movement in pixels this frame multiplied by sensitivity
+    MouseMotion {
+        axis: MouseAxis,
+        #[serde(default = "mult_default")]
+        sensitivity: f32
+    },
+    // scroll in lines this frame
+    MouseWheel {
+        axis: MouseAxis,
+        #[serde(default = "mult_default")]
+        mult: f32
+    },
+    // cursor position in the window from -1 to 1
+    CursorPosition {
+        axis: MouseAxis
+    },
     GamepadButton {
         #[serde(with = "crate::gamepad_button_serde")]
         button: GamepadButtonType
     },
+    // how far a pressure sensitive button or trigger is pulled, scaled so the threshold reads as 1 and counts as pressed
+    AnalogGamepadButton {
+        #[serde(with = "crate::gamepad_button_serde")]
+        button: GamepadButtonType,
+        #[serde(default = "mult_default")]
+        threshold: f32
+    },
     GamepadAxis {
         #[serde(with = "crate::gamepad_axis_serde")]
         axis: GamepadAxisType,
         #[serde(default = "mult_default")]
         mult: f32
+    },
+    // an on screen button from the touch controls resource
+    TouchButton {
+        button: String
+    },
+    // an axis of an on screen joystick from the touch controls resource, from -1 to 1 with y up
+    VirtualJoystick {
+        joystick: String,
+        axis: MouseAxis
+    },
+    // another element with a dead zone, response curve, sensitivity and inversion applied, in that order
+    Modified {
+        element: Box<InputElement>,
+        #[serde(default)]
+        dead_zone: f32,
+        #[serde(default)]
+        curve: ResponseCurve,
+        #[serde(default = "mult_default")]
+        sensitivity: f32,
+        #[serde(default)]
+        invert: bool
+    }
+}
+
+impl InputElement {
+    // wrap this element so modifiers can be applied, does nothing if already wrapped
+    pub fn modified(self) -> Self {
+        match self {
+            InputElement::Modified { .. } => self,
+            _ => InputElement::Modified { element: Box::new(self), dead_zone: 0., curve: ResponseCurve::Linear, sensitivity: 1., invert: false }
+        }
+    }
+
+    pub fn with_dead_zone(self, value: f32) -> Self {
+        match self.modified() {
+            InputElement::Modified { element, curve, sensitivity, invert, .. } => InputElement::Modified { element, dead_zone: value, curve, sensitivity, invert },
+            _ => unreachable!()
+        }
+    }
+
+    pub fn with_curve(self, value: ResponseCurve) -> Self {
+        match self.modified() {
+            InputElement::Modified { element, dead_zone, sensitivity, invert, .. } => InputElement::Modified { element, dead_zone, curve: value, sensitivity, invert },
+            _ => unreachable!()
+        }
+    }
+
+    pub fn with_sensitivity(self, value: f32) -> Self {
+        match self.modified() {
+            InputElement::Modified { element, dead_zone, curve, invert, .. } => InputElement::Modified { element, dead_zone, curve, sensitivity: value, invert },
+            _ => unreachable!()
+        }
+    }
+
+    pub fn inverted(self) -> Self {
+        match self.modified() {
+            InputElement::Modified { element, dead_zone, curve, sensitivity, invert } => InputElement::Modified { element, dead_zone, curve, sensitivity, invert: !invert },
+            _ => unreachable!()
+        }
+    }
+
+    // get the element that is actually read from a device, skipping any modifiers
+    pub fn source(&self) -> &InputElement {
+        match self {
+            InputElement::Modified { element, .. } => element.source(),
+            _ => self
+        }
+    }
+
+    // check if the value of this element always stays between -1 and 1
+    pub fn is_bounded(&self) -> bool {
+        match self {
+            InputElement::MouseMotion { .. } | InputElement::MouseWheel { .. } => false,
+            InputElement::GamepadAxis { mult, .. } => mult.abs() <= 1.,
+            InputElement::Modified { element, sensitivity, .. } => element.is_bounded() && sensitivity.abs() <= 1.,
+            _ => true
+        }
     }
+
+    // check if two elements read from the same key, button or axis, ignoring settings like mult
+    pub fn same_source(&self, other: &InputElement) -> bool {
+        match (self.source(), other.source()) {
+            (InputElement::Keyboard { key: a }, InputElement::Keyboard { key: b }) => a == b,
+            (InputElement::Mouse { button: a }, InputElement::Mouse { button: b }) => a == b,
+            (InputElement::MouseMotion { axis: a, .. }, InputElement::MouseMotion { axis: b, .. }) => a == b,
+            (InputElement::MouseWheel { axis: a, .. }, InputElement::MouseWheel { axis: b, .. }) => a == b,
+            (InputElement::CursorPosition { axis: a }, InputElement::CursorPosition { axis: b }) => a == b,
+            (InputElement::GamepadButton { button: a } | InputElement::AnalogGamepadButton { button: a, .. },
+                InputElement::GamepadButton { button: b } | InputElement::AnalogGamepadButton { button: b, .. }) => a == b,
+            (InputElement::GamepadAxis { axis: a, .. }, InputElement::GamepadAxis { axis: b, .. }) => a == b,
+            (InputElement::TouchButton { button: a }, InputElement::TouchButton { button: b }) => a == b,
+            (InputElement::VirtualJoystick { joystick: a, axis: c }, InputElement::VirtualJoystick { joystick: b, axis: d }) => a == b && c == d,
+            _ => false
+        }
+    }
+}
+
+fn mult_default() -> f32 { 1. }
+
+// Curves that remap the value of an element, keeping its sign
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(tag = "type")]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    // value to the power of exponent, above 1 gives more precision near the center
+    Power { exponent: f32 },
+    // smooth ease in and out
+    Smoothstep
+}
+
+impl ResponseCurve {
+    pub fn apply(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        let curved = match self {
+            ResponseCurve::Linear => magnitude,
+            ResponseCurve::Power { exponent } => magnitude.powf(*exponent),
+            ResponseCurve::Smoothstep => { let x = magnitude.min(1.); x * x * (3. - 2. * x) }
+        };
+        curved * value.signum()
+    }
+}
+
+// remove values closer to 0 than the dead zone and rescale the rest so output still starts at 0
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if dead_zone <= 0. { return value }
+    if value.abs() <= dead_zone { return 0. }
+    value.signum() * (value.abs() - dead_zone) / (1. - dead_zone).max(f32::EPSILON)
 }
 
-fn mult_default() -> f32 { 1. }
\ No newline at end of file
+// How a captured element is added to an action
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputCaptureMode {
+    // replace all of the actions inputs with the captured element
+    #[default]
+    Replace,
+    // add the captured element as another input
+    Append,
+    // replace the input at the given index
+    Set { index: usize },
+    // replace the positive or negative element of the axis at the given index
+    Positive { index: usize },
+    Negative { index: usize }
+}
+
+// An in progress capture, waiting for the next input to bind to an action
+#[derive(Debug, Clone)]
+pub struct InputCapture {
+    pub action: String,
+    pub mode: InputCaptureMode,
+    pub axis_threshold: f32,
+    pub cancel_key: Option<KeyCode>,
+    pub(crate) started: bool
+}
\ No newline at end of file
@@ -0,0 +1,284 @@
+use bevy::{prelude::*, input::{InputPlugin, gamepad::{GamepadConnectionEvent, GamepadConnection, GamepadInfo}}};
+
+use crate::{ConfigurableInputPlugin, InputPressedEvent, InputDepressedEvent, InputChangedEvent, update_inputs, mouse::AccumulatedMouse, recording::play_frame};
+
+// plugin that adds virtual devices and an input event log, for headless tests and bots
+pub struct InputTestingPlugin;
+impl Plugin for InputTestingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<VirtualDevices>()
+            .init_resource::<InputEventLog>()
+            .add_systems(PreUpdate, apply_virtual_devices.after(play_frame))
+            .add_systems(Update, log_input_events.after(update_inputs));
+    }
+}
+
+// create an app with only the plugins needed to run inputs without a window
+pub fn headless_input_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin, ConfigurableInputPlugin, InputTestingPlugin));
+    app
+}
+
+// run the given number of frames
+pub fn run_frames(app: &mut App, frames: usize) {
+    (0 .. frames).for_each(|_| app.update());
+}
+
+// A button on a virtual device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualButton {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(usize, GamepadButtonType)
+}
+
+impl From<KeyCode> for VirtualButton { fn from(value: KeyCode) -> Self { VirtualButton::Key(value) } }
+impl From<MouseButton> for VirtualButton { fn from(value: MouseButton) -> Self { VirtualButton::Mouse(value) } }
+
+// resource that fakes device input, everything given a number of frames is released after that many frames
+#[derive(Resource, Debug, Default, Clone)]
+pub struct VirtualDevices {
+    buttons: Vec<(VirtualButton, Option<usize>)>,
+    released: Vec<VirtualButton>,
+    axes: Vec<(GamepadAxis, f32, Option<usize>)>,
+    button_values: Vec<(GamepadButton, f32, Option<usize>)>,
+    motion: Vec<(Vec2, usize)>,
+    wheel: Vec<(Vec2, usize)>,
+    cursor: Option<Vec2>,
+    connect: Vec<usize>
+}
+
+impl VirtualDevices {
+    // hold a button until released
+    pub fn press(&mut self, button: impl Into<VirtualButton>) { self.hold(button.into(), None); }
+
+    // hold a button for the given number of frames
+    pub fn press_for(&mut self, button: impl Into<VirtualButton>, frames: usize) { self.hold(button.into(), Some(frames)); }
+
+    // press a button for a single frame
+    pub fn tap(&mut self, button: impl Into<VirtualButton>) { self.press_for(button, 1); }
+
+    pub fn release(&mut self, button: impl Into<VirtualButton>) {
+        let button = button.into();
+        self.buttons.retain(|(other, _)| *other != button);
+        self.released.push(button);
+    }
+
+    fn hold(&mut self, button: VirtualButton, frames: Option<usize>) {
+        self.buttons.retain(|(other, _)| *other != button);
+        self.buttons.push((button, frames));
+    }
+
+    // set a gamepad axis until released, connecting the gamepad if needed
+    pub fn set_axis(&mut self, gamepad: usize, axis: GamepadAxisType, value: f32) { self.axis(gamepad, axis, value, None); }
+
+    // set a gamepad axis for the given number of frames
+    pub fn set_axis_for(&mut self, gamepad: usize, axis: GamepadAxisType, value: f32, frames: usize) { self.axis(gamepad, axis, value, Some(frames)); }
+
+    pub fn release_axis(&mut self, gamepad: usize, axis: GamepadAxisType) { self.axis(gamepad, axis, 0., Some(0)); }
+
+    fn axis(&mut self, gamepad: usize, axis: GamepadAxisType, value: f32, frames: Option<usize>) {
+        self.connect_gamepad(gamepad);
+        let axis = GamepadAxis { gamepad: Gamepad::new(gamepad), axis_type: axis };
+        self.axes.retain(|(other, _, _)| *other != axis);
+        self.axes.push((axis, value, frames));
+    }
+
+    // set how far an analog gamepad button is pressed until released, without pressing the button itself
+    pub fn set_button_value(&mut self, gamepad: usize, button: GamepadButtonType, value: f32) { self.button_value(gamepad, button, value, None); }
+
+    // set how far an analog gamepad button is pressed for the given number of frames
+    pub fn set_button_value_for(&mut self, gamepad: usize, button: GamepadButtonType, value: f32, frames: usize) { self.button_value(gamepad, button, value, Some(frames)); }
+
+    pub fn release_button_value(&mut self, gamepad: usize, button: GamepadButtonType) { self.button_value(gamepad, button, 0., Some(0)); }
+
+    fn button_value(&mut self, gamepad: usize, button: GamepadButtonType, value: f32, frames: Option<usize>) {
+        self.connect_gamepad(gamepad);
+        let button = GamepadButton::new(Gamepad::new(gamepad), button);
+        self.button_values.retain(|(other, _, _)| *other != button);
+        self.button_values.push((button, value, frames));
+    }
+
+    // move the mouse by delta pixels every frame for the given number of frames
+    pub fn move_mouse(&mut self, delta: Vec2, frames: usize) { self.motion.push((delta, frames)); }
+
+    // scroll by the given lines every frame for the given number of frames
+    pub fn scroll(&mut self, lines: Vec2, frames: usize) { self.wheel.push((lines, frames)); }
+
+    // place the cursor from -1 to 1 with y up, or give it back to the real cursor with None
+    pub fn set_cursor(&mut self, position: Option<Vec2>) { self.cursor = position; }
+
+    // connect a gamepad so inputs can read from it, virtual gamepads can be read the frame after they connect
+    pub fn connect_gamepad(&mut self, gamepad: usize) {
+        if !self.connect.contains(&gamepad) { self.connect.push(gamepad); }
+    }
+
+    // release everything held by the virtual devices
+    pub fn release_all(&mut self) {
+        let buttons: Vec<VirtualButton> = self.buttons.drain(..).map(|(button, _)| button).collect();
+        self.released.extend(buttons);
+        self.axes.iter_mut().for_each(|(_, _, frames)| *frames = Some(0));
+        self.button_values.iter_mut().for_each(|(_, _, frames)| *frames = Some(0));
+        self.motion.clear();
+        self.wheel.clear();
+        self.cursor = None;
+    }
+}
+
+fn apply_virtual_devices(
+    mut devices: ResMut<VirtualDevices>,
+    gamepads: Res<Gamepads>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse_buttons: ResMut<Input<MouseButton>>,
+    mut mouse: ResMut<AccumulatedMouse>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+    mut gamepad_button_axis: ResMut<Axis<GamepadButton>>,
+    mut gamepad_axis: ResMut<Axis<GamepadAxis>>,
+    mut connections: EventWriter<GamepadConnectionEvent>
+) {
+    let devices = devices.as_mut();
+
+    // connect new gamepads
+    devices.connect.iter().filter(|id| !gamepads.contains(Gamepad::new(**id))).for_each(|id| {
+        connections.send(GamepadConnectionEvent::new(Gamepad::new(*id), GamepadConnection::Connected(GamepadInfo { name: "Virtual Gamepad".into() })));
+    });
+
+    // release buttons that ran out of frames
+    let expired: Vec<VirtualButton> = devices.buttons.iter().filter(|(_, frames)| *frames == Some(0)).map(|(button, _)| *button).collect();
+    devices.buttons.retain(|(_, frames)| *frames != Some(0));
+    let released: Vec<VirtualButton> = devices.released.drain(..).chain(expired).collect();
+    released.into_iter().for_each(|button| match button {
+        VirtualButton::Key(key) => keys.release(key),
+        VirtualButton::Mouse(button) => mouse_buttons.release(button),
+        VirtualButton::Gamepad(gamepad, button) => gamepad_buttons.release(GamepadButton::new(Gamepad::new(gamepad), button))
+    });
+
+    // press held buttons
+    devices.buttons.iter_mut().for_each(|(button, frames)| {
+        match *button {
+            VirtualButton::Key(key) => keys.press(key),
+            VirtualButton::Mouse(button) => mouse_buttons.press(button),
+            VirtualButton::Gamepad(gamepad, button) => gamepad_buttons.press(GamepadButton::new(Gamepad::new(gamepad), button))
+        }
+        if frames.is_some() { *frames = Some(frames.unwrap() - 1); }
+    });
+
+    // set axes, zeroing and removing the ones that ran out of frames
+    devices.axes.iter().for_each(|(axis, value, frames)| { gamepad_axis.set(*axis, if *frames == Some(0) { 0. } else { *value }); });
+    devices.axes.retain(|(_, _, frames)| *frames != Some(0));
+    devices.axes.iter_mut().for_each(|(_, _, frames)| if frames.is_some() { *frames = Some(frames.unwrap() - 1); });
+
+    // same for analog button values
+    devices.button_values.iter().for_each(|(button, value, frames)| { gamepad_button_axis.set(*button, if *frames == Some(0) { 0. } else { *value }); });
+    devices.button_values.retain(|(_, _, frames)| *frames != Some(0));
+    devices.button_values.iter_mut().for_each(|(_, _, frames)| if frames.is_some() { *frames = Some(frames.unwrap() - 1); });
+
+    // add mouse movement
+    devices.motion.iter_mut().chain(devices.wheel.iter_mut()).for_each(|(_, frames)| *frames = frames.saturating_sub(1));
+    mouse.motion += devices.motion.iter().map(|(delta, _)| *delta).sum::<Vec2>();
+    mouse.wheel += devices.wheel.iter().map(|(lines, _)| *lines).sum::<Vec2>();
+    devices.motion.retain(|(_, frames)| *frames > 0);
+    devices.wheel.retain(|(_, frames)| *frames > 0);
+    if devices.cursor.is_some() { mouse.cursor = devices.cursor.unwrap(); }
+}
+
+// What kind of event an input event log entry came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEventKind {
+    Pressed,
+    Depressed,
+    Changed
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputLogEntry {
+    pub frame: usize,
+    pub entity: Entity,
+    pub kind: InputEventKind,
+    pub name: String,
+    pub value: f32
+}
+
+// resource that keeps every input event so tests can check what happened and in what order
+#[derive(Resource, Debug, Default, Clone)]
+pub struct InputEventLog {
+    pub entries: Vec<InputLogEntry>,
+    pub frame: usize
+}
+
+// how close two values have to be to count as equal in assertions
+const VALUE_TOLERANCE: f32 = 0.0001;
+
+impl InputEventLog {
+    pub fn clear(&mut self) { self.entries.clear(); }
+
+    // get all entries of a kind for an action
+    pub fn get(&self, kind: InputEventKind, name: &str) -> Vec<&InputLogEntry> {
+        self.entries.iter().filter(|entry| entry.kind == kind && entry.name == name).collect()
+    }
+
+    // get all entries sent by one inputs component
+    pub fn from_entity(&self, entity: Entity) -> Vec<&InputLogEntry> {
+        self.entries.iter().filter(|entry| entry.entity == entity).collect()
+    }
+
+    // get the names of every pressed event in order
+    pub fn pressed_order(&self) -> Vec<&str> {
+        self.entries.iter().filter(|entry| entry.kind == InputEventKind::Pressed).map(|entry| entry.name.as_str()).collect()
+    }
+
+    // get every value an action changed to in order
+    pub fn values(&self, name: &str) -> Vec<f32> {
+        self.get(InputEventKind::Changed, name).iter().map(|entry| entry.value).collect()
+    }
+
+    #[track_caller]
+    pub fn assert_pressed(&self, name: &str) {
+        assert!(!self.get(InputEventKind::Pressed, name).is_empty(), "Expected {name} to be pressed, pressed events: {:?}", self.pressed_order());
+    }
+
+    #[track_caller]
+    pub fn assert_not_pressed(&self, name: &str) {
+        assert!(self.get(InputEventKind::Pressed, name).is_empty(), "Expected {name} to not be pressed, pressed events: {:?}", self.pressed_order());
+    }
+
+    #[track_caller]
+    pub fn assert_depressed(&self, name: &str) {
+        assert!(!self.get(InputEventKind::Depressed, name).is_empty(), "Expected {name} to be depressed");
+    }
+
+    // check that the given actions were pressed in this order, other presses in between are allowed
+    #[track_caller]
+    pub fn assert_pressed_order(&self, names: &[&str]) {
+        let order = self.pressed_order();
+        let mut remaining = order.iter();
+        let matches = names.iter().all(|name| remaining.any(|other| other == name));
+        assert!(matches, "Expected presses in order {names:?}, got {order:?}");
+    }
+
+    // check that an action changed to exactly these values in order
+    #[track_caller]
+    pub fn assert_values(&self, name: &str, expected: &[f32]) {
+        let values = self.values(name);
+        let matches = values.len() == expected.len() && values.iter().zip(expected).all(|(a, b)| (a - b).abs() <= VALUE_TOLERANCE);
+        assert!(matches, "Expected {name} to change to {expected:?}, got {values:?}");
+    }
+}
+
+fn log_input_events(
+    mut log: ResMut<InputEventLog>,
+    mut pressed: EventReader<InputPressedEvent>,
+    mut depressed: EventReader<InputDepressedEvent>,
+    mut changed: EventReader<InputChangedEvent>
+) {
+    let frame = log.frame;
+    let entries: Vec<InputLogEntry> = pressed.read().map(|event| (InputEventKind::Pressed, event.entity, &event.name, event.value))
+        .chain(depressed.read().map(|event| (InputEventKind::Depressed, event.entity, &event.name, event.value)))
+        .chain(changed.read().map(|event| (InputEventKind::Changed, event.entity, &event.name, event.value)))
+        .map(|(kind, entity, name, value)| InputLogEntry { frame, entity, kind, name: name.clone(), value })
+        .collect();
+    log.entries.extend(entries);
+    log.frame += 1;
+}
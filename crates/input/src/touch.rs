@@ -0,0 +1,145 @@
+use bevy::{prelude::*, utils::HashMap, window::PrimaryWindow};
+use serde::*;
+
+use crate::structs::apply_dead_zone;
+
+// plugin that turns touches into on screen button presses and joystick movement
+pub struct InputTouchPlugin;
+impl Plugin for InputTouchPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TouchControls>()
+            .add_systems(PreUpdate, update_touch_controls.after(bevy::input::InputSystem));
+    }
+}
+
+// An area of the window in fractions of its size from the top left (ex. the bottom right quarter is 0.5, 0.5, 0.5, 0.5)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct TouchRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32
+}
+
+impl TouchRegion {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self { Self { x, y, width, height } }
+
+    // check if a point in fractions of the window is inside this region
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.x && point.x <= self.x + self.width && point.y >= self.y && point.y <= self.y + self.height
+    }
+
+    pub fn center(&self) -> Vec2 { Vec2::new(self.x + self.width / 2., self.y + self.height / 2.) }
+}
+
+// A button that is pressed while any touch is inside its region
+#[derive(Debug, Clone)]
+pub struct TouchButton {
+    pub region: TouchRegion,
+    pub label: String,
+    pub pressed: bool
+}
+
+// A joystick that starts wherever a touch lands in its region and reads how far that touch has moved, up to radius pixels
+#[derive(Debug, Clone)]
+pub struct VirtualJoystick {
+    pub region: TouchRegion,
+    pub radius: f32,
+    pub dead_zone: f32,
+    // from -1 to 1 with y up
+    pub value: Vec2,
+    // where the current touch started in pixels from the top left
+    pub origin: Option<Vec2>,
+    touch: Option<u64>
+}
+
+// resource of all on screen controls by name, read by TouchButton and VirtualJoystick input elements
+#[derive(Resource, Debug, Clone)]
+pub struct TouchControls {
+    pub buttons: HashMap<String, TouchButton>,
+    pub joysticks: HashMap<String, VirtualJoystick>,
+    // controls are only drawn once visible, which happens automatically on the first touch
+    pub visible: bool,
+    // treat the left mouse button as a touch, useful for testing on desktop
+    pub emulate_with_mouse: bool
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self { buttons: HashMap::new(), joysticks: HashMap::new(), visible: false, emulate_with_mouse: false }
+    }
+}
+
+// id used for the emulated mouse touch
+const MOUSE_TOUCH_ID: u64 = u64::MAX;
+
+impl TouchControls {
+    pub fn add_button(&mut self, name: impl Into<String>, region: TouchRegion, label: impl Into<String>) -> &mut Self {
+        self.buttons.insert(name.into(), TouchButton { region, label: label.into(), pressed: false });
+        self
+    }
+
+    pub fn add_joystick(&mut self, name: impl Into<String>, region: TouchRegion, radius: f32) -> &mut Self {
+        self.joysticks.insert(name.into(), VirtualJoystick { region, radius, dead_zone: 0.1, value: Vec2::ZERO, origin: None, touch: None });
+        self
+    }
+
+    pub fn button(&self, name: &str) -> bool { self.buttons.get(name).is_some_and(|button| button.pressed) }
+    pub fn joystick(&self, name: &str) -> Vec2 { self.joysticks.get(name).map(|joystick| joystick.value).unwrap_or(Vec2::ZERO) }
+}
+
+pub(crate) fn update_touch_controls(
+    mut controls: ResMut<TouchControls>,
+    touches: Res<Touches>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    window: Query<&Window, With<PrimaryWindow>>
+) {
+    let controls = controls.as_mut();
+    if touches.any_just_pressed() { controls.visible = true; }
+
+    // get window size
+    let window = window.get_single();
+    let window = if window.is_ok() { window.unwrap() } else { return };
+    let size = Vec2::new(window.width(), window.height());
+    if size.x <= 0. || size.y <= 0. { return }
+
+    // get all touches as id, position and if they just started
+    let mut points: Vec<(u64, Vec2, bool)> = touches.iter().map(|touch| (touch.id(), touch.position(), touches.just_pressed(touch.id()))).collect();
+    if controls.emulate_with_mouse && mouse_buttons.pressed(MouseButton::Left) {
+        let cursor = window.cursor_position();
+        if cursor.is_some() { points.push((MOUSE_TOUCH_ID, cursor.unwrap(), mouse_buttons.just_pressed(MouseButton::Left))); }
+    }
+
+    // press buttons with a touch inside them
+    controls.buttons.values_mut().for_each(|button| {
+        button.pressed = points.iter().any(|(_, position, _)| button.region.contains(*position / size));
+    });
+
+    // release joysticks whose touch ended
+    controls.joysticks.values_mut().for_each(|joystick| {
+        if joystick.touch.is_some_and(|id| !points.iter().any(|(other, _, _)| *other == id)) {
+            joystick.touch = None;
+            joystick.origin = None;
+            joystick.value = Vec2::ZERO;
+        }
+    });
+
+    // start joysticks from new touches in their region
+    points.iter().filter(|(_, _, just_pressed)| *just_pressed).for_each(|(id, position, _)| {
+        let joystick = controls.joysticks.values_mut().find(|joystick| joystick.touch.is_none() && joystick.region.contains(*position / size));
+        if joystick.is_none() { return }
+        let joystick = joystick.unwrap();
+        joystick.touch = Some(*id);
+        joystick.origin = Some(*position);
+    });
+
+    // move active joysticks
+    controls.joysticks.values_mut().for_each(|joystick| {
+        let point = points.iter().find(|(id, _, _)| Some(*id) == joystick.touch);
+        let (_, position, _) = if point.is_some() { point.unwrap() } else { return };
+        let offset = (*position - joystick.origin.unwrap()) / joystick.radius.max(1.);
+        let offset = Vec2::new(offset.x, -offset.y).clamp_length_max(1.);
+        joystick.value = offset.normalize_or_zero() * apply_dead_zone(offset.length(), joystick.dead_zone);
+    });
+}
@@ -0,0 +1,126 @@
+use bevy::utils::HashMap;
+use serde::{*, de::{MapAccess, Visitor, IgnoredAny}};
+
+use crate::structs::*;
+
+// An error found while parsing an input description, with the action it happened in if known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputParseError {
+    pub action: Option<String>,
+    pub message: String,
+    pub line: usize,
+    pub column: usize
+}
+
+impl std::fmt::Display for InputParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.action.is_some() { write!(f, "action \"{}\": ", self.action.as_ref().unwrap())?; }
+        write!(f, "{} (line {}, column {})", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for InputParseError {}
+
+// parse an input description from json, failing on any unknown key, button, axis or field
+pub fn parse_input_description(content: &str) -> Result<InputDescription, InputParseError> {
+    let error = match serde_json::from_str::<InputDescription>(content) {
+        Ok(description) => return Ok(description),
+        Err(error) => error
+    };
+
+    // remove position from message
+    let full = error.to_string();
+    let message = full.strip_suffix(&format!(" at line {} column {}", error.line(), error.column())).unwrap_or(&full).to_string();
+
+    // find the action that failed by parsing each action on its own
+    let raw = serde_json::from_str::<RawDescription>(content).ok();
+    let action = raw.and_then(|raw| raw.elements.into_iter()
+        .find(|(_, value)| serde_json::from_value::<Vec<InputType>>(value.clone()).is_err_and(|error| error.to_string() == message))
+        .map(|(name, _)| name));
+
+    Err(InputParseError { action, message, line: error.line(), column: error.column() })
+}
+
+// get warnings for a parsed description, including actions written twice in the json
+pub fn input_description_warnings(content: &str, description: &InputDescription) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    // json objects keep only the last of duplicate keys, so check for them in the text
+    let mut names = serde_json::from_str::<RawKeys>(content).unwrap_or_default().elements.0;
+    names.sort();
+    names.windows(2).filter(|pair| pair[0] == pair[1]).for_each(|pair| {
+        warnings.push(format!("action \"{}\" is written more than once, only the last one is used", pair[0]));
+    });
+
+    warnings.extend(description.validate());
+    warnings.dedup();
+    warnings
+}
+
+impl InputDescription {
+    // get warnings for duplicate bindings and elements used by more than one action
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut names: Vec<&String> = self.elements.keys().collect();
+        names.sort();
+
+        names.iter().for_each(|name| {
+            let inputs = &self.elements[*name];
+
+            // same binding twice in one action
+            inputs.iter().enumerate().for_each(|(index, input)| {
+                if inputs[.. index].contains(input) { warnings.push(format!("action \"{name}\" has the same binding more than once")); }
+            });
+
+            // elements shared with later actions, chords are allowed to share elements
+            inputs.iter().filter(|input| !matches!(input, InputType::CHORD { elements, .. } if elements.len() > 1))
+                .flat_map(|input| input.elements())
+                .for_each(|element| {
+                    self.conflicts(name, element).iter().filter(|other| other.as_str() > name.as_str()).for_each(|other| {
+                        warnings.push(format!("actions \"{name}\" and \"{other}\" are both bound to {element:?}"));
+                    });
+                });
+        });
+
+        warnings.dedup();
+        warnings
+    }
+}
+
+// a description with the inputs of each action left as json
+#[derive(Deserialize)]
+struct RawDescription {
+    #[serde(default)]
+    elements: HashMap<String, serde_json::Value>
+}
+
+// only the action names of a description, including duplicates
+#[derive(Deserialize, Default)]
+struct RawKeys {
+    #[serde(default)]
+    elements: KeyList
+}
+
+#[derive(Default)]
+struct KeyList(Vec<String>);
+
+impl<'de> Deserialize<'de> for KeyList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_map(KeyListVisitor)
+    }
+}
+
+struct KeyListVisitor;
+impl<'de> Visitor<'de> for KeyListVisitor {
+    type Value = KeyList;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a map of actions")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
+        let mut keys = Vec::new();
+        while let Some((key, _)) = map.next_entry::<String, IgnoredAny>()? { keys.push(key); }
+        Ok(KeyList(keys))
+    }
+}
@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+use nebulousengine_input::structs::InputElement;
+
+fn round_trip(element: InputElement) -> InputElement {
+    let json = serde_json::to_string(&element).unwrap();
+    serde_json::from_str(&json).unwrap_or_else(|error| panic!("{json} did not load: {error}"))
+}
+
+#[test]
+fn named_buttons_round_trip() {
+    let elements = [
+        InputElement::Keyboard { key: KeyCode::Space },
+        InputElement::Mouse { button: MouseButton::Middle },
+        InputElement::GamepadButton { button: GamepadButtonType::DPadLeft },
+        InputElement::GamepadAxis { axis: GamepadAxisType::RightStickY, mult: -1. }
+    ];
+    elements.into_iter().for_each(|element| assert_eq!(round_trip(element.clone()), element));
+}
+
+#[test]
+fn other_buttons_keep_their_index() {
+    let elements = [
+        InputElement::Mouse { button: MouseButton::Other(4) },
+        InputElement::GamepadButton { button: GamepadButtonType::Other(17) },
+        InputElement::GamepadAxis { axis: GamepadAxisType::Other(3), mult: 1. }
+    ];
+    elements.into_iter().for_each(|element| assert_eq!(round_trip(element.clone()), element));
+}
+
+#[test]
+fn invalid_other_index_is_an_error() {
+    assert!(serde_json::from_str::<InputElement>(r#"{ "type": "Mouse", "button": "Other(x)" }"#).is_err());
+    assert!(serde_json::from_str::<InputElement>(r#"{ "type": "GamepadButton", "button": "Other(300)" }"#).is_err());
+}
@@ -0,0 +1,45 @@
+use bevy::{prelude::*, ecs::event::ManualEventReader};
+use nebulousengine_input::{structs::*, interactions::*, validation::parse_input_description, testing::*};
+
+#[test]
+fn interactions_only_read_their_binding() {
+    let mut app = headless_input_app();
+    app.world.spawn(Inputs::new(|inputs| {
+        inputs.insert("jump", vec![
+            InputType::scalar(InputElement::Keyboard { key: KeyCode::Space }).with_interaction(InputInteraction::Press { threshold: 0.5 }),
+            InputType::scalar(InputElement::Mouse { button: MouseButton::Left })
+        ]);
+    }));
+    let mut reader = ManualEventReader::<InputThresholdPressedEvent>::default();
+
+    // the mouse moves the action but has no interactions of its own
+    app.world.resource_mut::<VirtualDevices>().press_for(MouseButton::Left, 2);
+    (0 .. 4).for_each(|_| {
+        run_frames(&mut app, 1);
+        assert_eq!(reader.read(app.world.resource::<Events<InputThresholdPressedEvent>>()).count(), 0);
+    });
+
+    let mut pressed = 0;
+    app.world.resource_mut::<VirtualDevices>().press_for(KeyCode::Space, 2);
+    (0 .. 4).for_each(|_| {
+        run_frames(&mut app, 1);
+        pressed += reader.read(app.world.resource::<Events<InputThresholdPressedEvent>>()).count();
+    });
+    assert_eq!(pressed, 1);
+}
+
+#[test]
+fn interactions_are_written_on_bindings() {
+    let description = parse_input_description(r#"{ "elements": { "jump": [
+        { "type": "SCALAR", "element": { "type": "Keyboard", "key": "Space" }, "interactions": [{ "type": "Hold", "duration": 0.5 }] },
+        { "type": "SCALAR", "element": { "type": "GamepadButton", "button": "South" } }
+    ] } }"#).unwrap();
+    let jump = &description.elements["jump"];
+    assert_eq!(jump[0].interactions(), &[InputInteraction::Hold { duration: 0.5, threshold: 0.5 }]);
+    assert!(jump[1].interactions().is_empty());
+
+    // bindings without interactions are written without the field
+    let json = description.to_json().unwrap();
+    assert_eq!(json.matches("interactions").count(), 1);
+    assert!(parse_input_description(r#"{ "elements": {}, "interactions": {} }"#).is_err());
+}
@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+use nebulousengine_input::{recording::*, testing::*};
+
+#[derive(Resource, Default)]
+struct Finished(usize);
+
+fn count_finished(mut events: EventReader<InputPlaybackFinishedEvent>, mut finished: ResMut<Finished>) {
+    finished.0 += events.read().count();
+}
+
+#[test]
+fn empty_looping_recording_finishes_once() {
+    let mut app = headless_input_app();
+    app.init_resource::<Finished>().add_systems(Last, count_finished);
+    app.world.resource_mut::<InputRecorder>().play_looping(InputRecording::default());
+    run_frames(&mut app, 10);
+
+    assert_eq!(app.world.resource::<Finished>().0, 1);
+    assert!(!app.world.resource::<InputRecorder>().is_playing());
+}
+
+#[test]
+fn recording_with_other_buttons_loads() {
+    let frame = InputFrame {
+        mouse_buttons: vec![RecordedMouseButton(MouseButton::Other(4))],
+        gamepad_buttons: vec![RecordedGamepadButton { gamepad: 0, button: GamepadButtonType::Other(20) }],
+        ..Default::default()
+    };
+    let recording = InputRecording { frames: vec![frame] };
+    let loaded = InputRecording::from_json(&recording.to_json().unwrap()).unwrap();
+
+    assert_eq!(loaded.frames[0].mouse_buttons, recording.frames[0].mouse_buttons);
+    assert_eq!(loaded.frames[0].gamepad_buttons, recording.frames[0].gamepad_buttons);
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use nebulousengine_input::{structs::*, testing::*};
+
+fn spawn_player(app: &mut App) -> Entity {
+    app.world.spawn(Inputs::new(|inputs| {
+        inputs.insert("jump", vec![InputType::scalar(InputElement::Keyboard { key: KeyCode::Space })]);
+        inputs.insert("fire", vec![InputType::scalar(InputElement::Mouse { button: MouseButton::Left })]);
+        inputs.insert("steer", vec![InputType::scalar(InputElement::GamepadAxis { axis: GamepadAxisType::LeftStickX, mult: 1.0 })]);
+        inputs.insert("throttle", vec![InputType::scalar(InputElement::AnalogGamepadButton { button: GamepadButtonType::RightTrigger2, threshold: 0.8 })]);
+    })).id()
+}
+
+#[test]
+fn taps_and_holds_are_logged_in_order() {
+    let mut app = headless_input_app();
+    spawn_player(&mut app);
+
+    app.world.resource_mut::<VirtualDevices>().tap(KeyCode::Space);
+    run_frames(&mut app, 2);
+    app.world.resource_mut::<VirtualDevices>().press_for(MouseButton::Left, 3);
+    run_frames(&mut app, 5);
+
+    let log = app.world.resource::<InputEventLog>();
+    log.assert_pressed_order(&["jump", "fire"]);
+    log.assert_depressed("fire");
+    log.assert_values("jump", &[1.0, 0.0]);
+}
+
+#[test]
+fn buffered_press_stays_until_consumed() {
+    let mut app = headless_input_app();
+    let player = spawn_player(&mut app);
+
+    app.world.resource_mut::<VirtualDevices>().tap(KeyCode::Space);
+    run_frames(&mut app, 2);
+
+    let mut inputs = app.world.get_mut::<Inputs>(player).unwrap();
+    assert!(inputs.buffered_pressed("jump", 0.5));
+    assert!(inputs.consume_buffered("jump", 0.5));
+    assert!(!inputs.buffered_pressed("jump", 0.5));
+}
+
+#[test]
+fn virtual_gamepad_axes_and_analog_buttons() {
+    let mut app = headless_input_app();
+    spawn_player(&mut app);
+
+    // the gamepad connects on the first frame and can be read after
+    app.world.resource_mut::<VirtualDevices>().connect_gamepad(0);
+    run_frames(&mut app, 1);
+    app.world.resource_mut::<VirtualDevices>().set_axis_for(0, GamepadAxisType::LeftStickX, 0.5, 2);
+    run_frames(&mut app, 4);
+
+    // pull the trigger halfway to the threshold, then past it for a frame
+    app.world.resource_mut::<VirtualDevices>().set_button_value_for(0, GamepadButtonType::RightTrigger2, 0.4, 1);
+    run_frames(&mut app, 1);
+    app.world.resource_mut::<VirtualDevices>().set_button_value_for(0, GamepadButtonType::RightTrigger2, 0.9, 1);
+    run_frames(&mut app, 2);
+
+    let log = app.world.resource::<InputEventLog>();
+    log.assert_not_pressed("steer");
+    log.assert_values("steer", &[0.5, 0.0]);
+    log.assert_values("throttle", &[0.5, 1.0, 0.0]);
+    log.assert_depressed("throttle");
+}
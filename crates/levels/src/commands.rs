@@ -0,0 +1,60 @@
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
+
+use bevy::prelude::*;
+use nebulousengine_utils::console::{ConsoleCommand, ConsoleCommandPlugin};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::levels::Level;
+
+// plugin that adds the "level" console command for the level type T
+#[derive(Default)]
+pub struct LevelCommands<T: States + Default + Debug + Eq + PartialEq + Hash + DeserializeOwned>(pub PhantomData<T>);
+impl <T: States + Default + Debug + Eq + PartialEq + Hash + DeserializeOwned> Plugin for LevelCommands<T> {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(ConsoleCommandPlugin::<LevelCommand<T>>::default())
+            .add_systems(Update, handle_level_commands::<T>);
+    }
+}
+
+// console command to read or change the current level (ex. "level goto TestB")
+#[derive(Event, Debug, Clone)]
+pub enum LevelCommand<T: States> {
+    Goto(T),
+    Current
+}
+
+impl <T: States + DeserializeOwned> ConsoleCommand for LevelCommand<T> {
+    fn name() -> &'static str { "level" }
+    fn description() -> &'static str { "\"level goto <level>\" changes the level, \"level current\" prints it" }
+
+    fn parse(args: &[String]) -> Result<Self, String> {
+        match args.first().map(|a| a.as_str()) {
+            Some("goto") => {
+                let name = args.get(1);
+                let name = if name.is_some() { name.unwrap() } else { return Err("Usage: level goto <level>".into()) };
+                let level = serde_json::from_value::<T>(Value::String(name.clone()));
+                if level.is_ok() { Ok(Self::Goto(level.unwrap())) } else { Err(format!("No level named {name}")) }
+            },
+            Some("current") | None => Ok(Self::Current),
+            Some(other) => Err(format!("Unknown level subcommand {other}"))
+        }
+    }
+
+    fn complete(args: &[String]) -> Vec<String> {
+        if args.is_empty() { vec!["goto".into(), "current".into()] } else { Vec::new() }
+    }
+}
+
+fn handle_level_commands<T: States + Default + Debug + Eq + PartialEq + Hash + DeserializeOwned>(
+    mut events: EventReader<LevelCommand<T>>,
+    mut level: ResMut<Level<T>>
+) {
+    events.read().for_each(|event| {
+        match event {
+            LevelCommand::Goto(next) => { info!("Going to level {:?}", next); level.goto(next.clone()); },
+            LevelCommand::Current => info!("Current level: {:?}", level.current())
+        }
+    });
+}
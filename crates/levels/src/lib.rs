@@ -4,6 +4,7 @@ use bevy::prelude::*;
 use levels::Level;
 
 pub mod levels;
+pub mod commands;
 
 #[derive(Component, Debug, Default, Clone, Copy)]
 pub struct CurrentLevel;
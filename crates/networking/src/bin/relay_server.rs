@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, app::ScheduleRunnerPlugin, log::LogPlugin};
+use nebulousengine_networking::{GameNetworkingPlugin, connection::*, relay::*, wire::NetworkWireFormat};
+
+const HELP: &str = "usage: relay_server [options]
+  --address <addr>        address to host on (default 0.0.0.0:8050)
+  --timeout <seconds>     drop clients not heard from for this long (default 5)
+  --heartbeat <seconds>   time between pings (default 1)
+  --owned-entities <p>    what happens to a leaving clients entities: despawn or transfer (default despawn)
+  --tick-rate <hz>        updates per second (default 60)
+  --send-rate <hz>        times per second transforms are sent (default 30)
+  --event-log <path>      append every packet to this file for replaying
+  --json                  send json packets instead of binary, for debugging
+  --verbose               log every relayed packet
+  --help                  show this message";
+
+// headless server that relays packets between clients, owning which entities exist
+// cargo run --manifest-path crates/networking/Cargo.toml --bin relay_server -- --address 0.0.0.0:8050 --verbose
+fn main() {
+    let mut config = NetworkConfig::host("0.0.0.0:8050");
+    let mut tick_rate = 60.;
+    let mut verbose = false;
+
+    // read flags
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--address" => config.host_address = flag_value(&arg, args.next()),
+            "--timeout" => config.timeout = flag_value(&arg, args.next()),
+            "--heartbeat" => config.heartbeat_interval = flag_value(&arg, args.next()),
+            "--owned-entities" => config.owned_entities = match flag_value::<String>(&arg, args.next()).as_str() {
+                "despawn" => OwnedEntityPolicy::Despawn,
+                "transfer" => OwnedEntityPolicy::TransferToServer,
+                "keep" => fail("relays cannot hand kept entities back to returning players, use despawn or transfer"),
+                other => fail(&format!("unknown owned entity policy \"{other}\""))
+            },
+            "--tick-rate" => tick_rate = flag_value(&arg, args.next()),
+            "--send-rate" => config.network_tick_rate = flag_value(&arg, args.next()),
+            "--event-log" => config.event_log = Some(flag_value(&arg, args.next())),
+            "--json" => config.wire_format = NetworkWireFormat::Json,
+            "--verbose" => verbose = true,
+            "--help" => { println!("{HELP}"); return },
+            other => fail(&format!("unknown flag \"{other}\""))
+        }
+    }
+    if tick_rate <= 0. { fail("tick rate must be above 0"); }
+
+    App::new()
+        .add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1. / tick_rate))))
+        .add_plugins(LogPlugin::default())
+        .insert_resource(config)
+        .insert_resource(NetworkRelay { verbose, ..Default::default() })
+        .add_plugins((GameNetworkingPlugin, NetworkRelayPlugin))
+        .add_systems(Update, log_connection)
+        .run();
+}
+
+fn log_connection(
+    mut changed_events: EventReader<NetworkConnectionChangedEvent>,
+    mut failed_events: EventReader<NetworkConnectionFailedEvent>
+) {
+    changed_events.read().for_each(|event| info!("Relay is now {:?}", event.state));
+
+    // a relay that cannot host has nothing to do
+    let failed = failed_events.read().next();
+    if failed.is_some() {
+        let event = failed.unwrap();
+        error!("Relay failed to host on {}: {}", event.target, event.reason);
+        std::process::exit(1);
+    }
+}
+
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    let value = if value.is_some() { value.unwrap() } else { fail(&format!("{flag} needs a value")) };
+    value.parse().unwrap_or_else(|_| fail(&format!("invalid value \"{value}\" for {flag}")))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {message}\n{HELP}");
+    std::process::exit(1);
+}
@@ -3,7 +3,7 @@ use std::fmt::Debug;
 use bevy::{prelude::*, ecs::system::EntityCommands};
 use serde::{*, de::DeserializeOwned};
 
-use crate::{Networking, structs::{NetworkID, NetworkTransform, NetworkBuilderInfo, NetworkTransformTracker}, NetworkCallDespawnEvent, NetworkCallSpawnEvent, NetworkCallUpdateTransformEvent};
+use crate::{Networking, structs::{NetworkID, NetworkTransform, NetworkBuilderInfo, NetworkTransformTracker}, NetworkCallDespawnEvent, NetworkCallSpawnEvent};
 
 // create plugin to make the process of creating entity builder easy
 pub struct GameNetworkEntityBuilder<T: Serialize + DeserializeOwned + Debug + Clone + Event> {
@@ -62,28 +62,15 @@ pub fn handle_builder_queues<T: Serialize + DeserializeOwned + Debug + Clone + E
     mut commands: Commands,
     mut net: ResMut<Networking>,
     mut builder: NonSendMut<NetworkEntityBuilder<T>>,
-    mut net_query: Query<(Entity, &NetworkID, &mut Transform, &mut NetworkTransformTracker)>,
+    net_query: Query<(Entity, &NetworkID)>,
     mut spawn_events: EventReader<NetworkCallSpawnEvent>,
-    mut despawn_events: EventReader<NetworkCallDespawnEvent>,
-    mut transform_events: EventReader<NetworkCallUpdateTransformEvent>
+    mut despawn_events: EventReader<NetworkCallDespawnEvent>
 ) {
     // handle spawn events
     spawn_events.read().for_each(|event| {
         builder.queue_build(serde_json::from_value(event.build.clone()).unwrap(), event.transform.to_bevy(), event.network_id);
     });
 
-    // handle transform updates
-    transform_events.read().for_each(|event| {
-        let to_move = net_query.iter_mut().find(|(_, a, _, _)| a.net_id == event.network_id.net_id);
-        if to_move.is_some() {
-            let (_, _, mut transform, mut tracker) = to_move.unwrap();
-            transform.translation = event.transform.translation;
-            transform.rotation = event.transform.rotation;
-            transform.scale = event.transform.scale;
-            tracker.velocity = event.velocity;
-        }
-    });
-
     // handle despawn events
     despawn_events.read().for_each(|event| {
         builder.queue_despawn(event.network_id);
@@ -100,7 +87,7 @@ pub fn handle_builder_queues<T: Serialize + DeserializeOwned + Debug + Clone + E
             }, 
             owner.clone(), 
             NetworkBuilderInfo(serde_json::to_value(build).unwrap()),
-            NetworkTransformTracker { last_transform: *transform, velocity: Vec3::ZERO }
+            NetworkTransformTracker::new(*transform)
         ));
 
         // build entity
@@ -121,7 +108,7 @@ pub fn handle_builder_queues<T: Serialize + DeserializeOwned + Debug + Clone + E
     // run all queued despawns
     builder.queued_despawn.iter().for_each(|network_id| {
         // despawn with network ID
-        let to_despawn = net_query.iter().find(|(_, a, _, _)| a.net_id == network_id.net_id);
+        let to_despawn = net_query.iter().find(|(_, a)| **a == *network_id);
         if to_despawn.is_some() {
             commands.entity(to_despawn.unwrap().0).despawn_recursive();
         }
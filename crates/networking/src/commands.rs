@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use nebulousengine_utils::console::{ConsoleCommand, ConsoleCommandPlugin};
+
+use crate::{Networking, connection::NetworkConfig};
+
+// plugin that adds the "net" console command
+pub struct NetworkCommands;
+impl Plugin for NetworkCommands {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(ConsoleCommandPlugin::<NetCommand>::default())
+            .add_systems(Update, handle_net_commands);
+    }
+}
+
+// console command to inspect and control the network (ex. "net stats", "net join ws://127.0.0.1:8050")
+#[derive(Event, Debug, Clone)]
+pub enum NetCommand {
+    Stats,
+    Connections,
+    // address or url, the configured one if none
+    Host(Option<String>),
+    Join(Option<String>),
+    Leave
+}
+
+impl ConsoleCommand for NetCommand {
+    fn name() -> &'static str { "net" }
+    fn description() -> &'static str { "\"net stats\" prints network info, \"net connections\" lists connections, \"net host [address]\", \"net join [url]\" and \"net leave\" change the connection" }
+
+    fn parse(args: &[String]) -> Result<Self, String> {
+        match args.first().map(|a| a.as_str()) {
+            Some("stats") | None => Ok(Self::Stats),
+            Some("connections") => Ok(Self::Connections),
+            Some("host") => Ok(Self::Host(args.get(1).cloned())),
+            Some("join") => Ok(Self::Join(args.get(1).cloned())),
+            Some("leave") => Ok(Self::Leave),
+            Some(other) => Err(format!("Unknown net subcommand {other}"))
+        }
+    }
+
+    fn complete(args: &[String]) -> Vec<String> {
+        if args.is_empty() { vec!["stats".into(), "connections".into(), "host".into(), "join".into(), "leave".into()] } else { Vec::new() }
+    }
+}
+
+fn handle_net_commands(
+    mut events: EventReader<NetCommand>,
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>
+) {
+    events.read().for_each(|event| {
+        match event {
+            NetCommand::Stats => {
+                let role = if net.is_server() { "server" } else if net.is_client() { "client" } else { "none" };
+                info!("Role: {role}, state: {:?}, ID: {}, connections: {}, waiting events: {}", net.state(), net.my_id, net.connections.len(), net.waiting_events.len());
+            },
+            NetCommand::Connections => {
+                if net.connections.is_empty() { info!("No connections"); }
+                net.connections.iter().for_each(|(id, socket)| {
+                    info!("{id}: {:?}", socket.get_ref().peer_addr());
+                });
+            },
+            NetCommand::Host(address) => net.host(address.clone().unwrap_or(config.host_address.clone())),
+            NetCommand::Join(url) => net.join(url.clone().unwrap_or(config.join_url.clone())),
+            NetCommand::Leave => net.leave()
+        }
+    });
+}
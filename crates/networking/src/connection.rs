@@ -0,0 +1,392 @@
+use std::{net::{TcpListener, TcpStream}, sync::{Mutex, mpsc::{channel, Receiver, TryRecvError}}, time::Duration};
+
+use bevy::prelude::*;
+use tungstenite::{client, WebSocket, stream::MaybeTlsStream, Message, client::IntoClientRequest};
+use url::Url;
+
+use crate::{Networking, NetworkPacketWrapper, NetworkCallDespawnEvent, NetworkCallSetOwnerEvent, structs::NetworkID, wire::NetworkWireFormat};
+
+pub type NetworkClient = WebSocket<MaybeTlsStream<TcpStream>>;
+
+// Where and how to connect, insert before adding the networking plugin to change it
+#[derive(Resource, Debug, Clone)]
+pub struct NetworkConfig {
+    // address the server listens on when hosting
+    pub host_address: String,
+    // url of the server to join
+    pub join_url: String,
+    // what to do when the app starts
+    pub startup: NetworkStartup,
+    // seconds to wait for a server before joining fails
+    pub connect_timeout: f32,
+    // seconds between pings that keep connections alive
+    pub heartbeat_interval: f32,
+    // seconds without hearing anything before a connection is dropped
+    pub timeout: f32,
+    // what the server does with entities owned by a player that left
+    pub owned_entities: OwnedEntityPolicy,
+    // pass packets between clients instead of running them on the server, for dedicated servers without a game world
+    pub relay: bool,
+    // file the server appends every packet to, for replaying them later
+    pub event_log: Option<String>,
+    // format asked for when joining and allowed when hosting, json on either side makes a connection use json
+    pub wire_format: NetworkWireFormat,
+    // times per second changed transforms are sent
+    pub network_tick_rate: f32,
+    // size of the steps positions, scales and velocities are rounded to when sent
+    pub position_precision: f32,
+    // most transforms sent to a connection each network tick, the rest wait by priority
+    pub max_transforms_per_tick: usize,
+    // seconds remote entities are shown behind the newest transform received for them
+    pub interpolation_delay: f32,
+    // most seconds a remote entity keeps moving with its last velocity after its newest transform
+    pub max_extrapolation: f32,
+    // distance a received transform can jump before the entity snaps to it instead of moving there
+    pub snap_distance: f32
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            host_address: "127.0.0.1:8050".into(), join_url: "ws://127.0.0.1:8050".into(), startup: NetworkStartup::default(), connect_timeout: 5.,
+            heartbeat_interval: 1., timeout: 5., owned_entities: OwnedEntityPolicy::default(), relay: false, event_log: None,
+            wire_format: NetworkWireFormat::default(), network_tick_rate: 30., position_precision: 0.001, max_transforms_per_tick: 64,
+            interpolation_delay: 0.1, max_extrapolation: 0.25, snap_distance: 5.
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn host(address: impl Into<String>) -> Self {
+        Self { host_address: address.into(), startup: NetworkStartup::Host, ..Default::default() }
+    }
+
+    pub fn join(url: impl Into<String>) -> Self {
+        Self { join_url: url.into(), startup: NetworkStartup::Join, ..Default::default() }
+    }
+}
+
+// What networking does when the app starts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkStartup {
+    // wait for host or join to be called
+    None,
+    Host,
+    Join,
+    // join the server, hosting one if it cannot be reached
+    // two apps starting at the same time can both try to host, use Host and Join to choose roles instead
+    #[default]
+    JoinOrHost
+}
+
+// What happens to the network ID entities of a player that disconnects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OwnedEntityPolicy {
+    #[default]
+    Despawn,
+    // the server becomes the owner and keeps sending their transforms
+    TransferToServer,
+    // leave them as they are so they can be handed back with a NetworkSetOwnerEvent when the player returns
+    Keep
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkConnectionState {
+    #[default]
+    Disconnected,
+    // waiting for a server to accept a join
+    Connecting,
+    Connected,
+    Hosting
+}
+
+// events
+#[derive(Event, Debug, Clone, Copy)]
+pub struct NetworkConnectionChangedEvent { pub state: NetworkConnectionState, pub previous: NetworkConnectionState }
+#[derive(Event, Debug, Clone)]
+pub struct NetworkConnectionFailedEvent { pub target: String, pub reason: String }
+#[derive(Event, Debug, Clone)]
+pub struct NetworkClientDisconnectedEvent { pub id: u8, pub reason: String }
+#[derive(Event, Debug, Clone)]
+pub struct NetworkServerLostEvent { pub reason: String }
+// send on the server to change who owns an entity, builders are not run again so owned only components stay as they are
+// the entity gets a fresh net_id too, since the new owner may already use its old one
+#[derive(Event, Debug, Clone, Copy)]
+pub struct NetworkSetOwnerEvent { pub network_id: NetworkID, pub owner: u8 }
+
+// a host, join or leave call waiting for the next update
+#[derive(Debug, Clone)]
+pub(crate) enum NetworkRequest {
+    Host(String),
+    Join { url: String, fallback_host: Option<String> },
+    Leave
+}
+
+// a join running on another thread so the app does not freeze while connecting
+#[derive(Debug)]
+pub(crate) struct PendingJoin {
+    url: String,
+    fallback_host: Option<String>,
+    result: Mutex<Receiver<Result<(NetworkClient, NetworkWireFormat), String>>>
+}
+
+impl Networking {
+    // start a server on the given address (ex. "0.0.0.0:8050"), leaving any current connection
+    pub fn host(&mut self, address: impl Into<String>) { self.requests.push(NetworkRequest::Host(address.into())); }
+
+    // join the server at the given url (ex. "ws://127.0.0.1:8050"), leaving any current connection
+    pub fn join(&mut self, url: impl Into<String>) {
+        self.requests.push(NetworkRequest::Join { url: url.into(), fallback_host: None });
+        self.state = NetworkConnectionState::Connecting;
+    }
+
+    // join the server at the given url, hosting on the given address if it cannot be reached
+    pub fn join_or_host(&mut self, url: impl Into<String>, address: impl Into<String>) {
+        self.requests.push(NetworkRequest::Join { url: url.into(), fallback_host: Some(address.into()) });
+        self.state = NetworkConnectionState::Connecting;
+    }
+
+    // close the server or client connection
+    pub fn leave(&mut self) { self.requests.push(NetworkRequest::Leave); }
+
+    pub fn state(&self) -> NetworkConnectionState { self.state }
+
+    // get a fresh ID for an entity given to a new owner, these count down from the top so they never meet the ones builders count up from 0
+    pub(crate) fn transfer_id(&mut self, owner: u8) -> NetworkID {
+        self.transferred += 1;
+        NetworkID { owner, net_id: u64::MAX - self.transferred }
+    }
+
+    // remove a connection that left or stopped responding
+    pub(crate) fn drop_connection(&mut self, id: u8, reason: String) {
+        let socket = self.connections.remove(&id);
+        if socket.is_some() { let _ = socket.unwrap().close(None); }
+        self.formats.remove(&id);
+        self.links.remove(&id);
+        self.last_heard.remove(&id);
+        info!("Player {id} disconnected: {reason}");
+        self.dropped.push((id, reason));
+    }
+
+    // drop the connection to a server that closed or stopped responding
+    pub(crate) fn lose_server(&mut self, reason: String) {
+        warn!("Lost connection to server: {reason}");
+        self.disconnect();
+        self.lost_server = Some(reason);
+    }
+
+    // close all sockets and reset ids
+    fn disconnect(&mut self) {
+        if self.client.is_some() { let _ = self.client.as_mut().unwrap().close(None); }
+        self.connections.values_mut().for_each(|socket| { let _ = socket.close(None); });
+        self.server = None;
+        self.client = None;
+        self.connections.clear();
+        self.formats.clear();
+        self.links.clear();
+        self.last_heard.clear();
+        self.pending_join = None;
+        self.waiting_events.clear();
+        self.my_id = 0;
+        self.id_tracker = 0;
+        self.state = NetworkConnectionState::Disconnected;
+    }
+}
+
+pub(crate) fn start_networking(
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>
+) {
+    match config.startup {
+        NetworkStartup::None => {},
+        NetworkStartup::Host => net.host(config.host_address.clone()),
+        NetworkStartup::Join => net.join(config.join_url.clone()),
+        NetworkStartup::JoinOrHost => net.join_or_host(config.join_url.clone(), config.host_address.clone())
+    }
+}
+
+pub(crate) fn update_connection(
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>,
+    mut changed_events: EventWriter<NetworkConnectionChangedEvent>,
+    mut failed_events: EventWriter<NetworkConnectionFailedEvent>
+) {
+    // run requests in order
+    let requests: Vec<NetworkRequest> = net.requests.drain(..).collect();
+    requests.into_iter().for_each(|request| match request {
+        NetworkRequest::Host(address) => {
+            net.disconnect();
+            let result = host_server(&address);
+            if result.is_ok() {
+                net.server = Some(result.unwrap());
+                net.state = NetworkConnectionState::Hosting;
+                info!("Hosting server on {address}");
+            } else {
+                let reason = result.err().unwrap();
+                error!("Failed to host on {address}: {reason}");
+                failed_events.send(NetworkConnectionFailedEvent { target: address, reason });
+            }
+        },
+        NetworkRequest::Join { url, fallback_host } => {
+            net.disconnect();
+            let (sender, receiver) = channel();
+            let timeout = Duration::from_secs_f32(config.connect_timeout.max(0.001));
+            let (target, format) = (url.clone(), config.wire_format);
+            std::thread::spawn(move || { let _ = sender.send(join_server(&target, timeout, format)); });
+            net.pending_join = Some(PendingJoin { url, fallback_host, result: Mutex::new(receiver) });
+            net.state = NetworkConnectionState::Connecting;
+        },
+        NetworkRequest::Leave => {
+            if net.established() { info!("Left network"); }
+            net.disconnect();
+        }
+    });
+
+    // finish joins that are done
+    if net.pending_join.is_some() {
+        let result = net.pending_join.as_ref().unwrap().result.lock().unwrap().try_recv();
+        match result {
+            Err(TryRecvError::Empty) => {},
+            Ok(Ok((client, format))) => {
+                let url = net.pending_join.take().unwrap().url;
+                net.client = Some(client);
+                net.client_format = format;
+                net.state = NetworkConnectionState::Connected;
+                info!("Connected to {url} using {format:?} packets");
+            },
+            result => {
+                let pending = net.pending_join.take().unwrap();
+                let reason = match result { Ok(Err(reason)) => reason, _ => "connection thread stopped".into() };
+                net.state = NetworkConnectionState::Disconnected;
+
+                // host instead if allowed
+                if pending.fallback_host.is_some() {
+                    info!("Could not join {} ({reason}), hosting instead", pending.url);
+                    net.host(pending.fallback_host.unwrap());
+                } else {
+                    error!("Failed to join {}: {reason}", pending.url);
+                    failed_events.send(NetworkConnectionFailedEvent { target: pending.url, reason });
+                }
+            }
+        }
+    }
+
+    // send state changes
+    if net.last_state != net.state {
+        changed_events.send(NetworkConnectionChangedEvent { state: net.state, previous: net.last_state });
+        net.last_state = net.state;
+    }
+}
+
+fn host_server(address: &str) -> Result<TcpListener, String> {
+    let server = TcpListener::bind(address).map_err(|error| error.to_string())?;
+    server.set_nonblocking(true).map_err(|error| error.to_string())?;
+    Ok(server)
+}
+
+// connect to a server with a timeout, then make the socket non blocking
+// the server answers with the format to use, servers that do not answer get json
+fn join_server(url: &str, timeout: Duration, format: NetworkWireFormat) -> Result<(NetworkClient, NetworkWireFormat), String> {
+    let url = Url::parse(url).map_err(|error| format!("invalid url: {error}"))?;
+    if url.scheme() != "ws" { return Err(format!("unsupported scheme \"{}\", only ws:// is supported", url.scheme())) }
+    let addresses = url.socket_addrs(|| Some(80)).map_err(|error| format!("could not resolve address: {error}"))?;
+
+    // try each address until one connects
+    let mut reason = "no address found".to_string();
+    for address in addresses {
+        let stream = TcpStream::connect_timeout(&address, timeout);
+        let stream = match stream { Ok(stream) => stream, Err(error) => { reason = error.to_string(); continue } };
+        stream.set_read_timeout(Some(timeout)).map_err(|error| error.to_string())?;
+        stream.set_write_timeout(Some(timeout)).map_err(|error| error.to_string())?;
+
+        // handshake offering both formats, then switch to non blocking for reading every frame
+        let mut request = url.as_str().into_client_request().map_err(|error| error.to_string())?;
+        request.headers_mut().insert("Sec-WebSocket-Protocol", format.offer().parse().unwrap());
+        let (mut client, response) = client(request, MaybeTlsStream::Plain(stream)).map_err(|error| format!("handshake failed: {error}"))?;
+        let chosen = response.headers().get("Sec-WebSocket-Protocol").and_then(|protocol| protocol.to_str().ok()).and_then(NetworkWireFormat::from_protocol);
+        if let MaybeTlsStream::Plain(stream) = client.get_mut() {
+            stream.set_read_timeout(None).map_err(|error| error.to_string())?;
+            stream.set_nonblocking(true).map_err(|error| error.to_string())?;
+        }
+        return Ok((client, chosen.unwrap_or(NetworkWireFormat::Json)))
+    }
+    Err(reason)
+}
+
+// ping connections, send queued pongs and close frames, and drop anything that has been quiet too long
+pub(crate) fn update_heartbeats(
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>,
+    time: Res<Time>
+) {
+    if !net.established() { return }
+    let now = time.elapsed_seconds();
+    let ping = now - net.last_ping >= config.heartbeat_interval;
+    if ping { net.last_ping = now; }
+
+    // connections count as heard when first seen
+    let ids: Vec<u8> = if net.is_server() { net.connections.keys().copied().collect() } else { vec![0] };
+    ids.iter().for_each(|id| { net.last_heard.entry(*id).or_insert(now); });
+
+    // ping and flush
+    let net = net.as_mut();
+    net.connections.values_mut().for_each(|socket| {
+        if ping { let _ = socket.send(Message::Ping(Vec::new())); } else { let _ = socket.flush(); }
+    });
+    if net.client.is_some() {
+        let client = net.client.as_mut().unwrap();
+        if ping { let _ = client.send(Message::Ping(Vec::new())); } else { let _ = client.flush(); }
+    }
+
+    // drop quiet connections
+    let quiet: Vec<u8> = ids.into_iter().filter(|id| now - net.last_heard.get(id).copied().unwrap_or(now) > config.timeout).collect();
+    quiet.into_iter().for_each(|id| {
+        let reason = format!("no response for {} seconds", config.timeout);
+        if net.is_server() { net.drop_connection(id, reason) } else { net.lose_server(reason) }
+    });
+}
+
+// send disconnect events and apply the owned entity policy to entities of players that left
+pub(crate) fn handle_disconnects(
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>,
+    entities: Query<&NetworkID>,
+    mut disconnected_events: EventWriter<NetworkClientDisconnectedEvent>,
+    mut lost_events: EventWriter<NetworkServerLostEvent>,
+    mut despawn_events: EventWriter<NetworkCallDespawnEvent>,
+    mut owner_events: EventWriter<NetworkSetOwnerEvent>
+) {
+    let my_id = net.my_id;
+    net.dropped.drain(..).for_each(|(id, reason)| {
+        entities.iter().filter(|network_id| network_id.owner == id).for_each(|network_id| match config.owned_entities {
+            OwnedEntityPolicy::Despawn => despawn_events.send(NetworkCallDespawnEvent { network_id: *network_id }),
+            OwnedEntityPolicy::TransferToServer => owner_events.send(NetworkSetOwnerEvent { network_id: *network_id, owner: my_id }),
+            OwnedEntityPolicy::Keep => {}
+        });
+        disconnected_events.send(NetworkClientDisconnectedEvent { id, reason });
+    });
+
+    let lost = net.lost_server.take();
+    if lost.is_some() { lost_events.send(NetworkServerLostEvent { reason: lost.unwrap() }); }
+}
+
+// change entity owners, the server sends its changes to every client
+pub(crate) fn set_owners(
+    mut net: ResMut<Networking>,
+    mut entities: Query<&mut NetworkID>,
+    mut set_events: EventReader<NetworkSetOwnerEvent>,
+    mut call_events: EventReader<NetworkCallSetOwnerEvent>
+) {
+    let is_server = net.is_server();
+    let requests: Vec<(NetworkID, u8)> = set_events.read().filter(|_| {
+        if !is_server { warn!("Only the server can change entity owners"); }
+        is_server
+    }).map(|event| (event.network_id, event.owner)).collect();
+    let changes: Vec<(NetworkID, NetworkID)> = requests.into_iter().map(|(network_id, owner)| (network_id, net.transfer_id(owner))).collect();
+    changes.iter().for_each(|(network_id, new_id)| net.send(NetworkPacketWrapper::SetOwner { network_id: *network_id, new_id: *new_id }));
+
+    changes.into_iter().chain(call_events.read().map(|event| (event.network_id, event.new_id))).for_each(|(network_id, new_id)| {
+        let entity = entities.iter_mut().find(|other| **other == network_id);
+        if entity.is_some() { *entity.unwrap() = new_id; }
+    });
+}
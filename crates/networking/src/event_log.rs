@@ -0,0 +1,184 @@
+use std::{collections::VecDeque, fs::{File, OpenOptions}, io::{BufRead, BufReader, Write}, path::{Path, PathBuf}};
+
+use bevy::prelude::*;
+use serde::*;
+
+use crate::{Networking, NetworkPacketWrapper, connection::NetworkConfig};
+
+// A packet the server received or sent, with the servers tick and time when it did and the senders sequence number
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkLogEntry {
+    pub sender: u8,
+    pub tick: u64,
+    pub time: f64,
+    pub sequence: u64,
+    pub packet: NetworkPacketWrapper
+}
+
+// resource with the latest logged packets in tick order, appended to a file as one json entry per line
+// only the newest max entries are kept in memory, older ones are read back from the file when asked for
+#[derive(Resource, Debug)]
+pub struct NetworkEventLog {
+    pub max_entries: usize,
+    entries: VecDeque<NetworkLogEntry>,
+    // first tick with all of its entries still in memory
+    kept_from: u64,
+    path: Option<PathBuf>,
+    file: Option<File>
+}
+
+impl Default for NetworkEventLog {
+    fn default() -> Self {
+        Self { max_entries: 4096, entries: VecDeque::new(), kept_from: 0, path: None, file: None }
+    }
+}
+
+impl NetworkEventLog {
+    // open a log file, keeping the newest entries already in it and appending new ones after them
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let mut log = Self { path: Some(path.to_path_buf()), ..Default::default() };
+        if path.exists() { Self::read(path, |entry| { log.keep(entry); true })?; }
+        log.file = Some(OpenOptions::new().create(true).append(true).open(path).map_err(|error| error.to_string())?);
+        Ok(log)
+    }
+
+    // read all entries of a log file without opening it for writing
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<NetworkLogEntry>, String> {
+        let mut entries = Vec::new();
+        Self::read(path, |entry| { entries.push(entry); true })?;
+        Ok(entries)
+    }
+
+    // read the entries of a log file in order until the given function returns false
+    fn read(path: impl AsRef<Path>, mut f: impl FnMut(NetworkLogEntry) -> bool) -> Result<(), String> {
+        let file = File::open(path).map_err(|error| error.to_string())?;
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|error| error.to_string())?;
+            if line.trim().is_empty() { continue }
+            let entry = serde_json::from_str(&line).map_err(|error| format!("line {}: {error}", index + 1))?;
+            if !f(entry) { break }
+        }
+        Ok(())
+    }
+
+    pub fn append(&mut self, entry: NetworkLogEntry) {
+        if self.file.is_some() {
+            let result = writeln!(self.file.as_mut().unwrap(), "{}", serde_json::to_string(&entry).unwrap());
+            if result.is_err() { error!("Failed to write to event log: {}", result.err().unwrap()); }
+        }
+        self.keep(entry);
+    }
+
+    // add an entry to memory, dropping the oldest past max entries
+    fn keep(&mut self, entry: NetworkLogEntry) {
+        self.entries.push_back(entry);
+        while self.entries.len() > self.max_entries.max(1) {
+            let dropped = self.entries.pop_front().unwrap();
+            self.kept_from = dropped.tick + 1;
+        }
+    }
+
+    // entries from the first tick up to and including the last, read from the file if they are older than what is kept in memory
+    pub fn range(&self, first: u64, last: u64) -> Result<Vec<NetworkLogEntry>, String> {
+        if first < self.kept_from && self.path.is_some() {
+            let mut entries = Vec::new();
+            Self::read(self.path.as_ref().unwrap(), |entry| {
+                if entry.tick > last { return false }
+                if entry.tick >= first { entries.push(entry); }
+                true
+            })?;
+            return Ok(entries)
+        }
+        Ok(self.entries.iter().filter(|entry| entry.tick >= first && entry.tick <= last).cloned().collect())
+    }
+
+    // the newest entries, which are the only ones kept in memory
+    pub fn latest(&self) -> &VecDeque<NetworkLogEntry> { &self.entries }
+
+    pub fn last_tick(&self) -> Option<u64> { self.entries.back().map(|entry| entry.tick) }
+}
+
+pub(crate) fn write_event_log(
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>,
+    mut log: ResMut<NetworkEventLog>
+) {
+    let mut entries: Vec<NetworkLogEntry> = net.logged.drain(..).collect();
+    let path = if config.event_log.is_some() && net.is_server() { config.event_log.clone().unwrap() } else { return };
+
+    // open the log once hosting, keeping a failed path so it is not retried every frame
+    if log.path.as_deref() != Some(Path::new(&path)) {
+        *log = match NetworkEventLog::open(&path) {
+            Ok(opened) => { info!("Logging packets to {path}"); opened },
+            Err(error) => { error!("Failed to open event log {path}: {error}"); NetworkEventLog { path: Some(path.into()), ..Default::default() } }
+        };
+
+        // continue after the ticks already logged so the log stays in order
+        let last = log.last_tick();
+        if last.is_some() && last.unwrap() >= net.tick {
+            let offset = last.unwrap() + 1 - net.tick;
+            net.tick += offset;
+            entries.iter_mut().for_each(|entry| entry.tick += offset);
+        }
+    }
+
+    entries.into_iter().for_each(|entry| log.append(entry));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn entry(tick: u64) -> NetworkLogEntry {
+        NetworkLogEntry { sender: 1, tick, time: tick as f64 / 60., sequence: tick, packet: NetworkPacketWrapper::AckTransforms { tick } }
+    }
+
+    fn temp_log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{name}_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn old_ranges_are_read_from_the_file() {
+        let path = temp_log("event_log_range");
+        let mut log = NetworkEventLog::open(&path).unwrap();
+        log.max_entries = 3;
+        (0 .. 8).for_each(|tick| log.append(entry(tick)));
+        assert_eq!(log.latest().len(), 3);
+
+        let ticks = |entries: Vec<NetworkLogEntry>| entries.iter().map(|entry| entry.tick).collect::<Vec<u64>>();
+        assert_eq!(ticks(log.range(6, 7).unwrap()), vec![6, 7]);
+        assert_eq!(ticks(log.range(1, 3).unwrap()), vec![1, 2, 3]);
+        assert_eq!(ticks(log.range(4, 20).unwrap()), vec![4, 5, 6, 7]);
+        assert!(log.range(9, 20).unwrap().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn reopened_logs_continue_after_their_last_tick() {
+        let path = temp_log("event_log_reopen");
+        let run = |ticks: &[u64]| {
+            let mut app = App::new();
+            let mut net = Networking { server: Some(TcpListener::bind("127.0.0.1:0").unwrap()), ..Default::default() };
+            net.logged = ticks.iter().map(|tick| entry(*tick)).collect();
+            app
+                .insert_resource(net)
+                .insert_resource(NetworkConfig { event_log: Some(path.to_string_lossy().into()), ..Default::default() })
+                .init_resource::<NetworkEventLog>()
+                .add_systems(Update, write_event_log);
+            app.update();
+            app.world.resource::<Networking>().tick
+        };
+
+        // the second server starts at tick 0 again but is moved past what the first one logged
+        run(&[0, 1, 2]);
+        assert_eq!(run(&[0, 1]), 3);
+        let ticks: Vec<u64> = NetworkEventLog::load(&path).unwrap().iter().map(|entry| entry.tick).collect();
+        assert_eq!(ticks, vec![0, 1, 2, 3, 4]);
+        let _ = std::fs::remove_file(&path);
+    }
+}
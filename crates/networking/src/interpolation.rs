@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::structs::{NetworkTransform, NetworkTransformTracker};
+
+// most snapshots kept per entity, only the ones around the interpolation delay are needed
+const MAX_SNAPSHOTS: usize = 32;
+// how much of a later arrival the clock offset takes on per snapshot, so it follows clocks drifting apart without following every late packet
+const CLOCK_DRIFT: f64 = 0.01;
+// smallest time between snapshots on the local clock, so a shrinking clock offset cannot put a newer snapshot before an older one
+const MIN_SPACING: f32 = 0.001;
+// how far back a senders time can go before it is taken as a different clock
+const NEW_CLOCK: f64 = 1.;
+
+// A transform received for a remote entity with the time it was sent, moved onto the local clock
+#[derive(Debug, Clone, Copy)]
+pub struct NetworkSnapshot {
+    pub time: f32,
+    // time on the senders clock
+    pub sent: f64,
+    pub transform: NetworkTransform,
+    pub velocity: Vec3
+}
+
+impl NetworkTransformTracker {
+    // buffer a transform sent at the given time on the senders clock, clearing the buffer when it jumped further than the snap distance so it is not slid across
+    pub(crate) fn push_snapshot(&mut self, sent: f64, now: f32, transform: NetworkTransform, velocity: Vec3, snap_distance: f32) {
+        // a time far behind the newest is a different clock, like a new owner's, so start over with it
+        if self.snapshots.back().is_some_and(|last| last.sent - sent > NEW_CLOCK) {
+            self.snapshots.clear();
+            self.clock_offset = None;
+        }
+
+        // snapshots sent before the newest are out of date, and ones sent together replace each other
+        let last = self.snapshots.back().copied();
+        if last.is_some_and(|last| last.sent > sent) { return }
+        if last.is_some_and(|last| last.sent == sent) { self.snapshots.pop_back(); }
+        let mut time = self.local_time(sent, now);
+        if last.is_some_and(|last| last.sent < sent) { time = time.max(last.unwrap().time + MIN_SPACING); }
+
+        if last.is_some_and(|last| last.transform.translation.distance(transform.translation) > snap_distance) { self.snapshots.clear(); }
+        self.snapshots.push_back(NetworkSnapshot { time, sent, transform, velocity });
+        if self.snapshots.len() > MAX_SNAPSHOTS { self.snapshots.pop_front(); }
+        self.velocity = velocity;
+    }
+
+    // put a time from the sender on the local clock, using the fastest arrival seen so that snapshots keep the spacing they were sent with
+    fn local_time(&mut self, sent: f64, now: f32) -> f32 {
+        let offset = now as f64 - sent;
+        self.clock_offset = Some(match self.clock_offset {
+            Some(last) if offset >= last => last + (offset - last) * CLOCK_DRIFT,
+            _ => offset
+        });
+        (sent + self.clock_offset.unwrap()) as f32
+    }
+
+    // the newest snapshot received, ahead of what is shown by the interpolation delay
+    pub fn latest_snapshot(&self) -> Option<&NetworkSnapshot> { self.snapshots.back() }
+
+    // get the transform at a time between snapshots, extrapolating with the last velocity for at most max extrapolation seconds past them
+    // snapshots before the pair in use are dropped
+    pub(crate) fn sample(&mut self, time: f32, max_extrapolation: f32) -> Option<NetworkTransform> {
+        let next = self.snapshots.iter().position(|snapshot| snapshot.time > time);
+        let next = if next.is_some() { next.unwrap() } else {
+            let last = self.snapshots.back()?;
+            let extrapolate = (time - last.time).clamp(0., max_extrapolation.max(0.));
+            return Some(NetworkTransform { translation: last.transform.translation + last.velocity * extrapolate, ..last.transform })
+        };
+        if next == 0 { return Some(self.snapshots[0].transform) }
+
+        self.snapshots.drain(.. next - 1);
+        let (from, to) = (&self.snapshots[0], &self.snapshots[1]);
+        let amount = ((time - from.time) / (to.time - from.time)).clamp(0., 1.);
+        Some(NetworkTransform {
+            translation: from.transform.translation.lerp(to.transform.translation, amount),
+            rotation: from.transform.rotation.slerp(to.transform.rotation, amount),
+            scale: from.transform.scale.lerp(to.transform.scale, amount)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f32, angle: f32) -> NetworkTransform {
+        NetworkTransform { translation: Vec3::new(x, 0., 0.), rotation: Quat::from_rotation_y(angle), scale: Vec3::ONE }
+    }
+
+    // two snapshots sent a tenth of a second apart, the second arriving late
+    fn tracker() -> NetworkTransformTracker {
+        let mut tracker = NetworkTransformTracker::new(Transform::IDENTITY);
+        tracker.push_snapshot(10., 1., at(0., 0.), Vec3::X, 5.);
+        tracker.push_snapshot(10.1, 1.3, at(1., 1.), Vec3::X * 10., 5.);
+        tracker
+    }
+
+    #[test]
+    fn samples_between_snapshots_by_when_they_were_sent() {
+        let sampled = tracker().sample(1.05, 0.25).unwrap();
+        assert!(sampled.translation.distance(Vec3::new(0.5, 0., 0.)) < 0.01, "{}", sampled.translation);
+        assert!(sampled.rotation.angle_between(Quat::from_rotation_y(0.5)) < 0.01);
+    }
+
+    #[test]
+    fn extrapolation_stops_at_the_cap() {
+        let mut tracker = tracker();
+        let last = tracker.latest_snapshot().unwrap().time;
+        let sampled = tracker.sample(last + 0.1, 0.25).unwrap();
+        assert!(sampled.translation.distance(Vec3::new(2., 0., 0.)) < 0.01, "{}", sampled.translation);
+        let sampled = tracker.sample(last + 2., 0.25).unwrap();
+        assert!(sampled.translation.distance(Vec3::new(3.5, 0., 0.)) < 0.01, "{}", sampled.translation);
+    }
+
+    #[test]
+    fn jumps_past_the_snap_distance_are_not_slid_across() {
+        let mut tracker = tracker();
+        tracker.push_snapshot(10.2, 1.2, at(50., 0.), Vec3::ZERO, 5.);
+        assert_eq!(tracker.snapshots.len(), 1);
+        let sampled = tracker.sample(1.15, 0.25).unwrap();
+        assert_eq!(sampled.translation, Vec3::new(50., 0., 0.));
+    }
+}
@@ -1,7 +1,7 @@
 use std::{marker::PhantomData, hash::Hash, fmt::Debug};
 
 use bevy::prelude::*;
-use nebulousengine_levels::{*, levels::*};
+use nebulousengine_levels::{*, levels::*, commands::LevelCommands};
 use serde::{*, de::DeserializeOwned};
 
 use crate::{NetworkStateReceiveEvent, Networking};
@@ -12,7 +12,7 @@ pub struct SyncedLevel<T: States + Default + Debug + Eq + PartialEq + Hash + Ser
 impl <T: States + Default + Debug + Eq + PartialEq + Hash + Serialize + DeserializeOwned + 'static> Plugin for SyncedLevel<T> {
     fn build(&self, app: &mut App) {
         app
-            .add_plugins(Levels::<T>::default())
+            .add_plugins((Levels::<T>::default(), LevelCommands::<T>::default()))
             .add_systems(Update, control_state::<T>);
     }
 }
@@ -1,37 +1,97 @@
 use std::net::{TcpStream, TcpListener, SocketAddr};
 
 use bevy::{prelude::*, utils::HashMap};
+use commands::NetworkCommands;
+use connection::*;
+use event_log::*;
+use prediction::NetworkPredicted;
 use events::NetworkEventWrapper;
+use replication::*;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
 use structs::{NetworkID, NetworkTransform, NetworkBuilderInfo, NetworkTransformTracker};
-use tungstenite::{connect, WebSocket, stream::MaybeTlsStream, accept, Message};
-use url::Url;
+use tungstenite::{WebSocket, stream::MaybeTlsStream, accept_hdr, Message, handshake::server::{Request, Response}};
+use wire::*;
 
 pub mod builder;
+pub mod commands;
+pub mod connection;
+pub mod event_log;
 pub mod events;
+pub mod interpolation;
 pub mod levels;
+pub mod prediction;
+pub mod relay;
+pub mod replay;
+pub mod replication;
 pub mod structs;
+pub mod wire;
 
 #[derive(Resource, Debug, Default)]
 pub struct Networking {
     pub server: Option<TcpListener>,
     pub connections: HashMap<u8, WebSocket<TcpStream>>,
-    pub client: Option<WebSocket<MaybeTlsStream<TcpStream>>>,
+    pub client: Option<NetworkClient>,
     pub waiting_events: Vec<NetworkEventWrapper>,
     pub event_tickers: HashMap<String, usize>,
     pub my_id: u8,
-    pub id_tracker: u8
+    pub id_tracker: u8,
+    // the servers update count, clients follow it from the packets they receive
+    pub tick: u64,
+    pub(crate) time: f64,
+    // count of packets sent, never reset so the order of packets is known across reconnects
+    pub(crate) sequence: u64,
+    // format chosen at handshake for each connection, or with the server when a client
+    pub(crate) formats: HashMap<u8, NetworkWireFormat>,
+    pub(crate) client_format: NetworkWireFormat,
+    // transform batches sent to and received from each connection, the server is 0 for clients
+    pub(crate) links: HashMap<u8, ReplicationLink>,
+    pub(crate) batch_tick: u64,
+    // if this update is a network tick, when transforms are sent
+    pub(crate) network_tick: bool,
+    pub(crate) network_tick_timer: f32,
+    pub(crate) state: NetworkConnectionState,
+    pub(crate) last_state: NetworkConnectionState,
+    pub(crate) requests: Vec<NetworkRequest>,
+    pub(crate) pending_join: Option<PendingJoin>,
+    // when each connection was last heard from in seconds since startup, the server is 0 for clients
+    pub(crate) last_heard: HashMap<u8, f32>,
+    pub(crate) last_ping: f32,
+    pub(crate) dropped: Vec<(u8, String)>,
+    pub(crate) lost_server: Option<String>,
+    // count of entities given a new owner, for giving them fresh IDs
+    pub(crate) transferred: u64,
+    // packets from clients with who sent them, only filled when relaying
+    pub(crate) relayed: Vec<(u8, NetworkPacketWrapper)>,
+    // packets received or sent by the server this update, waiting to be written to the event log
+    pub(crate) logged: Vec<NetworkLogEntry>
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum NetworkPacketWrapper {
     Event(NetworkEventWrapper),
     SetNetID(u8),
     SpawnEntity { build: Value, transform: NetworkTransform, network_id: NetworkID },
     RemoveEntity { network_id: NetworkID },
     UpdateEntityTransform { network_id: NetworkID, transform: NetworkTransform, velocity: Vec3 },
-    SetState { state: Value }
+    SetState { state: Value },
+    // the new ID has the new owner and a fresh net_id
+    SetOwner { network_id: NetworkID, new_id: NetworkID },
+    // changed transforms of a network tick, against the batch with the baseline tick if there is one
+    TransformBatch { tick: u64, baseline: Option<u64>, precision: f32, updates: Vec<TransformDelta> },
+    AckTransforms { tick: u64 },
+    // an input for a predicted entity from its owner, and where the server put it after running its inputs up to the sequence
+    Input { network_id: NetworkID, sequence: u64, delta: f32, input: Value },
+    InputState { network_id: NetworkID, sequence: u64, transform: NetworkTransform, velocity: Vec3 }
+}
+
+// what is actually sent, a packet with the tick and time it was sent at and its place in the senders order
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkStampedPacket {
+    pub tick: u64,
+    pub time: f64,
+    pub sequence: u64,
+    pub packet: NetworkPacketWrapper
 }
 
 // event for new connection
@@ -45,30 +105,56 @@ pub struct NetworkCallSpawnEvent { pub build: Value, pub transform: NetworkTrans
 #[derive(Event, Debug)]
 pub struct NetworkCallDespawnEvent { pub network_id: NetworkID }
 
+// the tick and time are the senders, from when the transform was sent
 #[derive(Event, Debug)]
-pub struct NetworkCallUpdateTransformEvent { pub network_id: NetworkID, pub transform: NetworkTransform, pub velocity: Vec3 }
+pub struct NetworkCallUpdateTransformEvent { pub network_id: NetworkID, pub transform: NetworkTransform, pub velocity: Vec3, pub tick: u64, pub time: f64 }
 
 #[derive(Event, Debug)]
 pub struct NetworkStateReceiveEvent(Value);
 
+#[derive(Event, Debug)]
+pub struct NetworkCallSetOwnerEvent { pub network_id: NetworkID, pub new_id: NetworkID }
+
+#[derive(Event, Debug)]
+pub struct NetworkCallInputEvent { pub sender: u8, pub network_id: NetworkID, pub sequence: u64, pub delta: f32, pub input: Value }
+
+#[derive(Event, Debug)]
+pub struct NetworkCallReconcileEvent { pub network_id: NetworkID, pub sequence: u64, pub transform: NetworkTransform, pub velocity: Vec3 }
+
 impl Networking {
     pub fn established(&self) -> bool { self.server.is_some() || self.client.is_some() }
     pub fn is_server(&self) -> bool { self.server.is_some() }
     pub fn is_client(&self) -> bool { self.client.is_some() }
 
+    // stamp a packet with the current tick and next sequence number, ready to encode for each connection
+    pub(crate) fn stamp(&mut self, packet: NetworkPacketWrapper) -> EncodedPacket {
+        self.sequence += 1;
+        EncodedPacket::new(NetworkStampedPacket { tick: self.tick, time: self.time, sequence: self.sequence, packet })
+    }
+
+    pub(crate) fn format(&self, id: &u8) -> NetworkWireFormat { self.formats.get(id).copied().unwrap_or_default() }
+
     pub fn send(&mut self, packet: NetworkPacketWrapper) {
+        if !self.established() { return }
+
+        // log packets the server started, resent client packets are logged when received
+        if self.is_server() && packet_owner(&packet).unwrap_or(self.my_id) == self.my_id {
+            self.logged.push(NetworkLogEntry { sender: self.my_id, tick: self.tick, time: self.time, sequence: self.sequence + 1, packet: packet.clone() });
+        }
+
         // create message
-        let message = serde_json::to_string(&packet).unwrap();
+        let mut message = self.stamp(packet);
 
         if self.is_server() {
             // send to each connection
-            self.connections.iter_mut().for_each(|(_id, socket)| {
-                let send_result = socket.send(Message::Text(message.clone()));
+            let formats = &self.formats;
+            self.connections.iter_mut().for_each(|(id, socket)| {
+                let send_result = socket.send(message.get(formats.get(id).copied().unwrap_or_default()));
                 if send_result.is_err() { error!("Failed server broadcast send {}", send_result.err().unwrap()); }
             });
         } else {
             let client = self.client.as_mut().unwrap();
-            let send_result = client.send(Message::Text(message));
+            let send_result = client.send(message.get(self.client_format));
             if send_result.is_err() { error!("Failed client send {}", send_result.err().unwrap()); }
         }
     }
@@ -77,36 +163,46 @@ impl Networking {
         // make sure is server
         if !self.is_server() { error!("server_send_targeted is only allowed for servers!"); return }
 
-        // generate message
-        let message = serde_json::to_string(&packet).unwrap();
-
-        // get connection of given ID
-        let connection = self.connections.get_mut(id);
-        let connection = if connection.is_some() { connection.unwrap() } else { error!("No connection with ID: {}", id); return };
+        // make sure connection exists
+        if !self.connections.contains_key(id) { error!("No connection with ID: {}", id); return }
 
         // send packet
-        let send_result = connection.send(Message::Text(message));
+        let message = self.stamp(packet).get(self.format(id));
+        let send_result = self.connections.get_mut(id).unwrap().send(message);
         if send_result.is_err() { error!("Failed client send {}", send_result.err().unwrap()); }
     }
 
-    // todo function that sends to all except a specific client
+    // send to every connection except the one with the given ID
+    pub fn server_send_except(&mut self, id: &u8, packet: NetworkPacketWrapper) {
+        if !self.is_server() { error!("server_send_except is only allowed for servers!"); return }
+        let mut message = self.stamp(packet);
+        let formats = &self.formats;
+        self.connections.iter_mut().filter(|(other, _)| *other != id).for_each(|(other, socket)| {
+            let send_result = socket.send(message.get(formats.get(other).copied().unwrap_or_default()));
+            if send_result.is_err() { error!("Failed server send {}", send_result.err().unwrap()); }
+        });
+    }
+
     pub fn broadcast_ignorable(&mut self, ignore_addr: SocketAddr, packet: NetworkPacketWrapper) {
+        if !self.established() { return }
+
         // create message
-        let message = serde_json::to_string(&packet).unwrap();
+        let mut message = self.stamp(packet);
 
         if self.is_server() {
             // send to each connection
-            self.connections.iter_mut().for_each(|(_id, socket)| {
+            let formats = &self.formats;
+            self.connections.iter_mut().for_each(|(id, socket)| {
                 // skip if ignored
-                if socket.get_ref().peer_addr().unwrap() == ignore_addr { return }
+                if socket.get_ref().peer_addr().is_ok_and(|addr| addr == ignore_addr) { return }
 
                 // send message
-                let send_result = socket.send(Message::Text(message.clone()));
+                let send_result = socket.send(message.get(formats.get(id).copied().unwrap_or_default()));
                 if send_result.is_err() { error!("Failed server broadcast send {}", send_result.err().unwrap()); }
             });
         } else {
             let client = self.client.as_mut().unwrap();
-            let send_result = client.send(Message::Text(message));
+            let send_result = client.send(message.get(self.client_format));
             if send_result.is_err() { error!("Failed client send {}", send_result.err().unwrap()); }
         }
     }
@@ -121,47 +217,33 @@ impl Plugin for GameNetworkingPlugin {
             .add_event::<NetworkCallDespawnEvent>()
             .add_event::<NetworkCallUpdateTransformEvent>()
             .add_event::<NetworkStateReceiveEvent>()
+            .add_event::<NetworkCallSetOwnerEvent>()
+            .add_event::<NetworkSetOwnerEvent>()
+            .add_event::<NetworkCallInputEvent>()
+            .add_event::<NetworkCallReconcileEvent>()
+            .add_event::<NetworkClientDisconnectedEvent>()
+            .add_event::<NetworkServerLostEvent>()
+            .add_event::<NetworkConnectionChangedEvent>()
+            .add_event::<NetworkConnectionFailedEvent>()
             .insert_resource(Networking::default())
-            .add_systems(Startup, setup)
-            .add_systems(Update, (accept_connections, recv_packets, update_transforms));
-    }
-}
-
-fn setup(mut net: ResMut<Networking>) {
-    let client = connect(Url::parse("ws://127.0.0.1:8050").unwrap());
-
-    // if a connection was made, save it, otherwise, create the server
-    if client.is_ok() {
-        let mut client = client.unwrap().0;
-
-        // make stream non blocking
-        let stream = client.get_mut();
-        match stream {
-            MaybeTlsStream::Plain(stream) => {
-                let nonblocking = stream.set_nonblocking(true);
-                if nonblocking.is_err() { error!("Set non blocking failed with error: {}", nonblocking.err().unwrap()); }
-            },
-            _ => error!("Unknown maybe tls stream type: {:?}", stream),
-        }
-
-        // save client
-        net.client = Some(client);
-        info!("Client connected to server!");
-    } else {
-        // create server
-        let server = TcpListener::bind("127.0.0.1:8050").unwrap();
-        let nonblocking = server.set_nonblocking(true);
-        net.server = Some(server);
-        info!("Created server!");
-
-        if nonblocking.is_err() { error!("Set non blocking failed with error: {}", nonblocking.err().unwrap()); }
+            .init_resource::<NetworkConfig>()
+            .add_plugins(NetworkCommands)
+            .init_resource::<NetworkEventLog>()
+            .add_systems(Startup, start_networking)
+            .add_systems(First, (advance_tick, advance_network_tick).after(bevy::time::TimeSystem))
+            .add_systems(PreUpdate, update_connection)
+            .add_systems(Update, (accept_connections, recv_packets, update_transforms.after(recv_packets)))
+            .add_systems(Update, (replicate_transforms, send_transform_acks).after(recv_packets).after(update_transforms))
+            .add_systems(Last, write_event_log)
+            .add_systems(Update, (update_heartbeats, handle_disconnects, set_owners).chain().after(recv_packets).after(accept_connections));
     }
 }
 
 fn accept_connections(
     mut net: ResMut<Networking>, 
     networked_entities: Query<(&NetworkBuilderInfo, &Transform, &NetworkID)>,
-    mut connect_events: EventWriter<NetworkServerNewConnectionEvent>
+    mut connect_events: EventWriter<NetworkServerNewConnectionEvent>,
+    config: Res<NetworkConfig>
 ) {
     // accept incoming connections
     if net.is_server() {
@@ -176,11 +258,21 @@ fn accept_connections(
                 Ok(stream) => {
                     // accept connection
                     println!("Accepting connection {:?}", stream.peer_addr());
-                    let socket = accept(stream);
+                    let mut format = NetworkWireFormat::Json;
+                    let socket = accept_hdr(stream, |request: &Request, mut response: Response| {
+                        // choose a format from the offered subprotocols, answering with it if the client offered any
+                        let offer = request.headers().get("Sec-WebSocket-Protocol").and_then(|offer| offer.to_str().ok());
+                        format = config.wire_format.choose(offer);
+                        if offer.is_some() { response.headers_mut().insert("Sec-WebSocket-Protocol", format.protocol().parse().unwrap()); }
+                        Ok(response)
+                    });
                     match socket {
                         Ok(socket) => {
+                            // accepted streams start blocking, which would freeze reads every frame
+                            let nonblocking = socket.get_ref().set_nonblocking(true);
+                            if nonblocking.is_err() { error!("Set non blocking failed with error: {}", nonblocking.err().unwrap()); }
                             net_id_offset += 1;
-                            new_connections.insert(net_id_offset, socket);
+                            new_connections.insert(net_id_offset, (socket, format));
                         },
                         Err(error) => error!("Accepting connection failed with error: {}", error)
                     };
@@ -190,24 +282,27 @@ fn accept_connections(
         }
 
         // send entities to new connections
-        new_connections.iter_mut().for_each(|(conn_id, connection)| {
+        new_connections.iter_mut().for_each(|(conn_id, (connection, format))| {
             // set net id
-            let wrapper = NetworkPacketWrapper::SetNetID(*conn_id);
-            let send_result = connection.send(Message::Text(serde_json::to_string(&wrapper).unwrap()));
+            let message = net.stamp(NetworkPacketWrapper::SetNetID(*conn_id)).get(*format);
+            let send_result = connection.send(message);
             if send_result.is_err() { error!("Error in new connection net ID update: {:?}", send_result); }
             connect_events.send(NetworkServerNewConnectionEvent(*conn_id));
 
             // send networked entities
             networked_entities.for_each(|(info, transform, id)| {
-                let wrapper = NetworkPacketWrapper::SpawnEntity { build: info.0.clone(), transform: NetworkTransform::from_bevy(transform), network_id: *id };
-                let send_result = connection.send(Message::Text(serde_json::to_string(&wrapper).unwrap()));
+                let message = net.stamp(NetworkPacketWrapper::SpawnEntity { build: info.0.clone(), transform: NetworkTransform::from_bevy(transform), network_id: *id }).get(*format);
+                let send_result = connection.send(message);
                 if send_result.is_err() { error!("Error in new connection exist entities update: {:?}", send_result); }
             });
         });
 
         // update connections
         net.id_tracker = net_id_offset;
-        net.connections.extend(new_connections);
+        new_connections.into_iter().for_each(|(id, (connection, format))| {
+            net.connections.insert(id, connection);
+            net.formats.insert(id, format);
+        });
     }
 }
 
@@ -217,125 +312,277 @@ fn recv_packets(
     mut despawn_events: EventWriter<NetworkCallDespawnEvent>,
     mut transform_events: EventWriter<NetworkCallUpdateTransformEvent>,
     mut state_events: EventWriter<NetworkStateReceiveEvent>,
+    mut owner_events: EventWriter<NetworkCallSetOwnerEvent>,
+    mut input_events: EventWriter<NetworkCallInputEvent>,
+    mut reconcile_events: EventWriter<NetworkCallReconcileEvent>,
+    predicted: Query<&NetworkID, With<NetworkPredicted>>,
+    config: Res<NetworkConfig>,
+    time: Res<Time>
 ) {
     // objects for updating net
     let mut new_waiting_events = Vec::new();
     let mut my_id = 0 as u8;
+    let now = time.elapsed_seconds();
+    let (tick, stamp_time) = (net.tick, net.time);
+    let mut server_tick = 0;
+    let mut heard: Vec<u8> = Vec::new();
+    let mut closed: Vec<(u8, String)> = Vec::new();
+    let mut relayed: Vec<(u8, NetworkPacketWrapper)> = Vec::new();
+    let mut logged: Vec<NetworkLogEntry> = Vec::new();
 
     // receive from connections
-    if net.is_server() {
-        net.connections.iter_mut().for_each(|(_, stream)| {
+    let is_server = net.is_server();
+    let net_ref = net.as_mut();
+    let links = &mut net_ref.links;
+    if is_server {
+        net_ref.connections.iter_mut().for_each(|(id, stream)| {
+            // closed sockets have no address, reading finds out why
+            let addr = stream.get_ref().peer_addr().unwrap_or(SocketAddr::from(([0, 0, 0, 0], 0)));
+
             // read all waiting
-            while let Ok(read) = stream.read() {
-                // read message
-                process_incoming(
-                    read, &mut new_waiting_events, 
-                    &mut spawn_events, &mut despawn_events, &mut transform_events, &mut state_events,
-                    &mut my_id, stream.get_ref().peer_addr().unwrap()
-                );
+            loop {
+                match stream.read() {
+                    Ok(read) => {
+                        heard.push(*id);
+                        let (packet, close) = unpack_message(read, addr);
+                        if close { closed.push((*id, "left".into())); break }
+                        let packet = if packet.is_some() { packet.unwrap() } else { continue };
+                        let (sequence, stamp) = (packet.sequence, (packet.tick, packet.time));
+
+                        unbatch(packet.packet, links.entry(*id).or_default()).into_iter().for_each(|packet| {
+                            // log with the servers tick, the senders sequence keeps their order
+                            logged.push(NetworkLogEntry { sender: *id, tick, time: stamp_time, sequence, packet: packet.clone() });
+
+                            // relays pass packets on instead of running them
+                            if config.relay { relayed.push((*id, packet)); return }
+
+                            // inputs keep who sent them so players can only move their own entities, and only the server says where predicted entities are and who owns what
+                            match packet {
+                                NetworkPacketWrapper::Input { network_id, sequence, delta, input } => { input_events.send(NetworkCallInputEvent { sender: *id, network_id, sequence, delta, input }); return },
+                                NetworkPacketWrapper::InputState { .. } => { warn!("Rejected input state from {id}"); return },
+                                NetworkPacketWrapper::SetOwner { network_id, .. } => { warn!("Rejected owner change of {network_id:?} from {id}"); return },
+                                NetworkPacketWrapper::UpdateEntityTransform { network_id, .. } if predicted.iter().any(|other| *other == network_id) => { warn!("Rejected transform of predicted entity {network_id:?} from {id}"); return },
+                                _ => {}
+                            }
+                            process_packet(
+                                packet, &mut new_waiting_events, 
+                                &mut spawn_events, &mut despawn_events, &mut transform_events, &mut state_events, &mut owner_events, &mut reconcile_events,
+                                &mut my_id, addr, stamp
+                            );
+                        });
+                    },
+                    Err(tungstenite::Error::Io(error)) if error.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(error) => { closed.push((*id, error.to_string())); break }
+                }
             }
         });
-    } else {
+    } else if net_ref.client.is_some() {
         // read messages from client
-        let client = net.client.as_mut().unwrap();
-        while let Ok(read) = client.read() {
-            let addr = match client.get_ref() {
-                MaybeTlsStream::Plain(client) => client.peer_addr().unwrap(),
-                _ => todo!(),
-            };
-
-            // read message
-            process_incoming(
-                read, &mut new_waiting_events, 
-                &mut spawn_events, &mut despawn_events, &mut transform_events, &mut state_events,
-                &mut my_id, addr
-            );
+        let client = net_ref.client.as_mut().unwrap();
+        // only plain sockets can be read, anything else loses the server before reading
+        let addr = match client.get_ref() {
+            MaybeTlsStream::Plain(client) => client.peer_addr().unwrap_or(SocketAddr::from(([0, 0, 0, 0], 0))),
+            _ => {
+                error!("Server connection is not a plain socket and cannot be read from");
+                closed.push((0, "unsupported stream".into()));
+                SocketAddr::from(([0, 0, 0, 0], 0))
+            }
+        };
+        while closed.is_empty() {
+            match client.read() {
+                Ok(read) => {
+                    heard.push(0);
+                    let (packet, close) = unpack_message(read, addr);
+                    if close { closed.push((0, "server closed".into())); continue }
+                    let packet = if packet.is_some() { packet.unwrap() } else { continue };
+                    server_tick = server_tick.max(packet.tick);
+                    let stamp = (packet.tick, packet.time);
+                    unbatch(packet.packet, links.entry(0).or_default()).into_iter().for_each(|packet| process_packet(
+                        packet, &mut new_waiting_events, 
+                        &mut spawn_events, &mut despawn_events, &mut transform_events, &mut state_events, &mut owner_events, &mut reconcile_events,
+                        &mut my_id, addr, stamp
+                    ));
+                },
+                Err(tungstenite::Error::Io(error)) if error.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(error) => closed.push((0, error.to_string()))
+            }
         }
     }
 
     // update net
     net.waiting_events.extend(new_waiting_events);
+    net.relayed.extend(relayed);
+    net.logged.extend(logged);
     if my_id != 0 { net.my_id = my_id; }
+    if server_tick > net.tick { net.tick = server_tick; }
+    heard.into_iter().for_each(|id| { net.last_heard.insert(id, now); });
+    closed.into_iter().for_each(|(id, reason)| if is_server { net.drop_connection(id, reason) } else { net.lose_server(reason) });
+}
+
+// unpack a stamped packet from a message, also returning if the message closed the connection
+fn unpack_message(message: Message, addr: SocketAddr) -> (Option<NetworkStampedPacket>, bool) {
+    match message {
+        Message::Text(_) | Message::Binary(_) => {
+            let packet = NetworkWireFormat::decode(&message).unwrap();
+            if packet.is_err() { error!("Failed to unpack packet from {addr} with error: {}", packet.err().unwrap()); return (None, false) }
+            (packet.ok(), false)
+        },
+
+        // pongs are sent automatically, both only keep the connection alive
+        Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => (None, false),
+        Message::Close(_) => (None, true)
+    }
 }
 
-fn process_incoming(
-    message: Message, 
+fn process_packet(
+    wrapper: NetworkPacketWrapper, 
     waiting_events: &mut Vec<NetworkEventWrapper>, 
     spawn_events: &mut EventWriter<NetworkCallSpawnEvent>, 
     despawn_events: &mut EventWriter<NetworkCallDespawnEvent>,
     transform_events: &mut EventWriter<NetworkCallUpdateTransformEvent>,
     state_events: &mut EventWriter<NetworkStateReceiveEvent>,
+    owner_events: &mut EventWriter<NetworkCallSetOwnerEvent>,
+    reconcile_events: &mut EventWriter<NetworkCallReconcileEvent>,
     my_id: &mut u8,
-    addr: SocketAddr
+    addr: SocketAddr,
+    (tick, time): (u64, f64)
 ) {
-    // match to message type
-    match message {
-        Message::Text(text) => {
-            // unpack packet wrapper
-            let wrapper = serde_json::from_str::<NetworkPacketWrapper>(text.as_str());
-            if wrapper.is_ok() {
-                let wrapper = wrapper.unwrap();
-
-                // match wrapper to create function
-                match wrapper {
-                    // events
-                    NetworkPacketWrapper::Event(mut wrapper) => {
-                        wrapper.from_addr = Some(addr);
-                        waiting_events.push(wrapper);
-                    },
+    // match wrapper to create function
+    match wrapper {
+        // events
+        NetworkPacketWrapper::Event(mut wrapper) => {
+            wrapper.from_addr = Some(addr);
+            waiting_events.push(wrapper);
+        },
 
-                    NetworkPacketWrapper::SetNetID(id) => { *my_id = id; },
+        NetworkPacketWrapper::SetNetID(id) => { *my_id = id; },
 
-                    // entity control
-                    NetworkPacketWrapper::SpawnEntity { build, transform, network_id } => {
-                        spawn_events.send(NetworkCallSpawnEvent { build, transform, network_id });
-                    },
+        // entity control
+        NetworkPacketWrapper::SpawnEntity { build, transform, network_id } => {
+            spawn_events.send(NetworkCallSpawnEvent { build, transform, network_id });
+        },
 
-                    NetworkPacketWrapper::RemoveEntity { network_id } => {
-                        despawn_events.send(NetworkCallDespawnEvent { network_id });
-                    },
+        NetworkPacketWrapper::RemoveEntity { network_id } => {
+            despawn_events.send(NetworkCallDespawnEvent { network_id });
+        },
 
-                    NetworkPacketWrapper::UpdateEntityTransform { network_id, transform, velocity } => {
-                        transform_events.send(NetworkCallUpdateTransformEvent { network_id, transform, velocity });
-                    },
+        NetworkPacketWrapper::UpdateEntityTransform { network_id, transform, velocity } => {
+            transform_events.send(NetworkCallUpdateTransformEvent { network_id, transform, velocity, tick, time });
+        },
 
-                    NetworkPacketWrapper::SetState { state } => {
-                        state_events.send(NetworkStateReceiveEvent(state))
-                    }
-                }
-            } else {
-                error!("Failed to unpack input text to wrapper with error: {}", wrapper.err().unwrap());
-            }
+        NetworkPacketWrapper::SetState { state } => {
+            state_events.send(NetworkStateReceiveEvent(state))
+        },
+
+        NetworkPacketWrapper::SetOwner { network_id, new_id } => {
+            owner_events.send(NetworkCallSetOwnerEvent { network_id, new_id });
+        },
+
+        NetworkPacketWrapper::InputState { network_id, sequence, transform, velocity } => {
+            reconcile_events.send(NetworkCallReconcileEvent { network_id, sequence, transform, velocity });
         },
 
-        Message::Binary(_) => todo!(),
-        Message::Ping(_) => todo!(),
-        Message::Pong(_) => todo!(),
-        Message::Close(_) => todo!(),
-        Message::Frame(_) => todo!(),
+        // unpacked by unbatch before getting here, inputs are taken out by the server before too
+        NetworkPacketWrapper::TransformBatch { .. } | NetworkPacketWrapper::AckTransforms { .. } | NetworkPacketWrapper::Input { .. } => {}
     }
 }
 
-fn update_transforms(
+// turn transform batches into an update per entity and apply acknowledgements, passing other packets through
+fn unbatch(packet: NetworkPacketWrapper, link: &mut ReplicationLink) -> Vec<NetworkPacketWrapper> {
+    match packet {
+        NetworkPacketWrapper::TransformBatch { tick, baseline, precision, updates } => link.receive(tick, baseline, precision, updates),
+        NetworkPacketWrapper::AckTransforms { tick } => { link.acknowledge(tick); Vec::new() },
+        packet => vec![packet]
+    }
+}
+
+// get the player whose entity a packet changes
+pub(crate) fn packet_owner(packet: &NetworkPacketWrapper) -> Option<u8> {
+    match packet {
+        NetworkPacketWrapper::SpawnEntity { network_id, .. } |
+        NetworkPacketWrapper::RemoveEntity { network_id } |
+        NetworkPacketWrapper::UpdateEntityTransform { network_id, .. } |
+        NetworkPacketWrapper::Input { network_id, .. } => Some(network_id.owner),
+        _ => None
+    }
+}
+
+fn advance_tick(
     mut net: ResMut<Networking>,
-    mut updated_network_entities: Query<(&NetworkID, &mut NetworkTransformTracker, &mut Transform)>,
     time: Res<Time>
 ) {
-    updated_network_entities.for_each_mut(|(id, mut tracker, mut transform)| {
-        // if owned, process transform update
+    net.tick += 1;
+    net.time = time.elapsed_seconds_f64();
+}
+
+fn update_transforms(
+    net: Res<Networking>,
+    config: Res<NetworkConfig>,
+    mut updated_network_entities: Query<(&NetworkID, &mut NetworkTransformTracker, &mut Transform, Has<NetworkPredicted>)>,
+    mut transform_events: EventReader<NetworkCallUpdateTransformEvent>,
+    time: Res<Time>
+) {
+    // buffer received transforms at the time they were sent, owned entities are moved straight to them since nothing else would
+    // servers run inputs for predicted entities, so they ignore transforms for them
+    let (now, is_server) = (time.elapsed_seconds(), net.is_server());
+    transform_events.read().for_each(|event| {
+        let entity = updated_network_entities.iter_mut().find(|(id, _, _, _)| **id == event.network_id);
+        if entity.is_none() { return }
+        let (id, mut tracker, mut transform, predicted) = entity.unwrap();
+        if predicted && is_server { return }
         if net.my_id == id.owner {
-            // make sure transform changed
-            let velocity = (transform.translation - tracker.last_transform.translation) / time.delta_seconds();
-            if tracker.last_transform == *transform && tracker.velocity == velocity { return }
+            *transform = event.transform.to_bevy();
             tracker.last_transform = *transform;
-            tracker.velocity = velocity;
+        } else {
+            tracker.push_snapshot(event.time, now, event.transform, event.velocity, config.snap_distance);
+        }
+    });
 
-            // send transform update
-            net.send(NetworkPacketWrapper::UpdateEntityTransform { network_id: *id, transform: NetworkTransform::from_bevy(&transform), velocity });
+    if time.delta_seconds() <= 0. { return }
+    updated_network_entities.for_each_mut(|(id, mut tracker, mut transform, predicted)| {
+        // if owned or simulated here from inputs, track velocity for replicate_transforms to send
+        if net.my_id == id.owner || (predicted && is_server) {
+            tracker.velocity = (transform.translation - tracker.last_transform.translation) / time.delta_seconds();
+            tracker.last_transform = *transform;
         }
-        // otherwise, update velocity
+        // otherwise, show where it was the interpolation delay ago
         else {
-            transform.translation += tracker.velocity * time.delta_seconds();
+            let sampled = tracker.sample(now - config.interpolation_delay, config.max_extrapolation);
+            if sampled.is_some() { *transform = sampled.unwrap().to_bevy(); }
         }
     });
 }
+
+// send changed transforms each network tick, clients send the entities they own and servers send everything
+fn replicate_transforms(
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>,
+    mut entities: Query<(&NetworkID, &Transform, &mut NetworkTransformTracker, Option<&NetworkReplication>)>,
+    predicted: Query<&NetworkID, With<NetworkPredicted>>,
+    time: Res<Time>
+) {
+    if !net.network_tick || !net.established() { return }
+    let (now, my_id, is_server) = (time.elapsed_seconds(), net.my_id, net.is_server());
+
+    // entities allowed to send this tick, with how long they have waited scaled by priority
+    let mut candidates: Vec<(NetworkID, QuantizedTransform, f32)> = Vec::new();
+    entities.for_each_mut(|(id, transform, mut tracker, replication)| {
+        // predicted entities are moved by the server from inputs, so only it sends them and from where it put them
+        let is_predicted = predicted.iter().any(|predicted| predicted == id);
+        if !is_server && (id.owner != my_id || is_predicted) { return }
+        let replication = replication.cloned().unwrap_or_default();
+        if replication.send_rate.is_some_and(|rate| now - tracker.last_sent < 1. / rate) { return }
+        tracker.waiting += replication.priority;
+
+        // pass on the newest transform of remote entities rather than the delayed one shown
+        let latest = if id.owner != my_id && !is_predicted { tracker.latest_snapshot().map(|snapshot| snapshot.transform) } else { None };
+        let transform = latest.unwrap_or(NetworkTransform::from_bevy(transform));
+        candidates.push((*id, QuantizedTransform::new(&transform, tracker.velocity, config.position_precision), tracker.waiting));
+    });
+
+    let sent = net.replicate(candidates, config.max_transforms_per_tick, config.position_precision);
+    entities.for_each_mut(|(id, _, mut tracker, _)| if sent.contains(id) {
+        tracker.waiting = 0.;
+        tracker.last_sent = now;
+    });
+}
@@ -0,0 +1,237 @@
+use std::{collections::VecDeque, fmt::Debug};
+
+use bevy::{prelude::*, utils::{HashMap, HashSet}};
+use serde::{*, de::DeserializeOwned};
+
+use crate::{*, structs::*};
+
+// most inputs waiting for the server, older ones are dropped so a server that never answers does not grow them forever
+const MAX_PENDING_INPUTS: usize = 256;
+// longest time one input can move an entity, so clients cannot claim long frames to move further
+const MAX_INPUT_DELTA: f32 = 0.25;
+// most input time an entity can save up, enough for inputs that arrive bunched together but not to send many at once to move faster
+const MAX_INPUT_BUDGET: f32 = 0.5;
+
+// plugin that moves owned entities with the NetworkPredicted component as soon as input is given while the server stays in charge of them
+// the simulate function runs on the client when input is given and again when reconciling, and on the server for the inputs it receives
+// relays cannot simulate, so prediction needs a server with a game world
+pub struct NetworkPredictionPlugin<I: Serialize + DeserializeOwned + Debug + Clone + Send + Sync + 'static> {
+    simulate: fn(transform: &mut Transform, input: &I, delta: f32)
+}
+
+impl<I: Serialize + DeserializeOwned + Debug + Clone + Send + Sync + 'static> NetworkPredictionPlugin<I> {
+    pub fn new(simulate: fn(transform: &mut Transform, input: &I, delta: f32)) -> Self {
+        Self { simulate }
+    }
+}
+
+impl<I: Serialize + DeserializeOwned + Debug + Clone + Send + Sync + 'static> Plugin for NetworkPredictionPlugin<I> {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(NetworkPrediction::<I>::new(self.simulate))
+            .add_systems(Update, (reconcile_inputs::<I>, predict_inputs::<I>, simulate_inputs::<I>).chain().after(recv_packets).before(update_transforms));
+    }
+}
+
+// marks an entity as moved by inputs through the prediction plugin instead of by its owner sending transforms
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct NetworkPredicted;
+
+// An input given for an entity, numbered so the server can say which inputs it has run
+#[derive(Debug, Clone)]
+pub struct NetworkInput<I> {
+    pub network_id: NetworkID,
+    pub sequence: u64,
+    pub delta: f32,
+    pub input: I
+}
+
+// resource that takes inputs for owned entities and keeps the ones the server has not run yet
+#[derive(Resource)]
+pub struct NetworkPrediction<I: Send + Sync + 'static> {
+    simulate: fn(transform: &mut Transform, input: &I, delta: f32),
+    queued: Vec<(NetworkID, I)>,
+    pending: VecDeque<NetworkInput<I>>,
+    sequence: u64,
+    // last input run for each entity on the server, and the entities whose state has not been sent back since
+    processed: HashMap<NetworkID, u64>,
+    unechoed: HashSet<NetworkID>,
+    // time each entity can still be moved by inputs, growing with the servers time so inputs cannot add up to more than it
+    budgets: HashMap<NetworkID, f32>,
+    // distance the last reconcile moved an entity from where it was predicted
+    pub last_correction: f32
+}
+
+impl<I: Send + Sync + 'static> NetworkPrediction<I> {
+    pub fn new(simulate: fn(transform: &mut Transform, input: &I, delta: f32)) -> Self {
+        Self {
+            simulate, queued: Vec::new(), pending: VecDeque::new(), sequence: 0,
+            processed: HashMap::new(), unechoed: HashSet::new(), budgets: HashMap::new(), last_correction: 0.
+        }
+    }
+
+    // give an input for an owned entity, it is run with this updates delta time
+    pub fn input(&mut self, network_id: NetworkID, input: I) { self.queued.push((network_id, input)); }
+
+    // inputs sent to the server that it has not run yet, oldest first
+    pub fn pending(&self) -> &VecDeque<NetworkInput<I>> { &self.pending }
+}
+
+// move owned entities back to where the server says they are and run the inputs it has not seen again on top
+fn reconcile_inputs<I: Serialize + DeserializeOwned + Debug + Clone + Send + Sync + 'static>(
+    net: Res<Networking>,
+    mut prediction: ResMut<NetworkPrediction<I>>,
+    mut entities: Query<(&NetworkID, &mut Transform, &mut NetworkTransformTracker), With<NetworkPredicted>>,
+    mut reconcile_events: EventReader<NetworkCallReconcileEvent>
+) {
+    if net.is_server() { return }
+    reconcile_events.read().for_each(|event| {
+        prediction.pending.retain(|input| input.network_id != event.network_id || input.sequence > event.sequence);
+        let entity = entities.iter_mut().find(|(id, _, _)| **id == event.network_id);
+        if entity.is_none() { return }
+        let (_, mut transform, mut tracker) = entity.unwrap();
+
+        let predicted = transform.translation;
+        *transform = event.transform.to_bevy();
+        let simulate = prediction.simulate;
+        prediction.pending.iter().filter(|input| input.network_id == event.network_id).for_each(|input| simulate(&mut transform, &input.input, input.delta));
+        prediction.last_correction = predicted.distance(transform.translation);
+
+        // a correction is not movement, so it should not show up in the velocity
+        tracker.last_transform = *transform;
+    });
+}
+
+// run queued inputs on owned entities straight away, sending them to the server when a client
+fn predict_inputs<I: Serialize + DeserializeOwned + Debug + Clone + Send + Sync + 'static>(
+    mut net: ResMut<Networking>,
+    mut prediction: ResMut<NetworkPrediction<I>>,
+    mut entities: Query<(&NetworkID, &mut Transform), With<NetworkPredicted>>,
+    time: Res<Time>
+) {
+    // clamped like the server does so both run inputs the same
+    let delta = time.delta_seconds().min(MAX_INPUT_DELTA);
+    let queued: Vec<(NetworkID, I)> = prediction.queued.drain(..).collect();
+    queued.into_iter().for_each(|(network_id, input)| {
+        let entity = entities.iter_mut().find(|(id, _)| **id == network_id && id.owner == net.my_id);
+        if entity.is_none() { warn!("Input given for entity {network_id:?} that is not owned or predicted"); return }
+        (prediction.simulate)(&mut entity.unwrap().1, &input, delta);
+
+        // servers run their own inputs with nothing to reconcile
+        if !net.is_client() { return }
+        prediction.sequence += 1;
+        let sequence = prediction.sequence;
+        net.send(NetworkPacketWrapper::Input { network_id, sequence, delta, input: serde_json::to_value(&input).unwrap() });
+        prediction.pending.push_back(NetworkInput { network_id, sequence, delta, input });
+        if prediction.pending.len() > MAX_PENDING_INPUTS { prediction.pending.pop_front(); }
+    });
+}
+
+// run inputs from the players that own each entity, sending back where they ended up once per network tick
+fn simulate_inputs<I: Serialize + DeserializeOwned + Debug + Clone + Send + Sync + 'static>(
+    mut net: ResMut<Networking>,
+    mut prediction: ResMut<NetworkPrediction<I>>,
+    mut entities: Query<(&NetworkID, &mut Transform, &NetworkTransformTracker), With<NetworkPredicted>>,
+    mut input_events: EventReader<NetworkCallInputEvent>,
+    time: Res<Time>
+) {
+    // rebuilt from the entities each update so removed ones are forgotten
+    let delta = time.delta_seconds();
+    let budgets = entities.iter().map(|(id, _, _)| (*id, (prediction.budgets.get(id).copied().unwrap_or(0.) + delta).min(MAX_INPUT_BUDGET))).collect();
+    prediction.budgets = budgets;
+
+    input_events.read().for_each(|event| {
+        if event.sender != event.network_id.owner { warn!("Rejected input from {} for entity {:?} it does not own", event.sender, event.network_id); return }
+        if prediction.processed.get(&event.network_id).is_some_and(|processed| *processed >= event.sequence) { return }
+        let input = serde_json::from_value::<I>(event.input.clone());
+        if input.is_err() { error!("Failed to read input from {}: {}", event.sender, input.err().unwrap()); return }
+        let entity = entities.iter_mut().find(|(id, _, _)| **id == event.network_id);
+        if entity.is_none() { return }
+
+        // inputs past the budget are not run, but still count as processed so the player is sent back to where the server has them
+        let delta = event.delta.clamp(0., MAX_INPUT_DELTA);
+        let budget = prediction.budgets.entry(event.network_id).or_default();
+        if *budget >= delta {
+            *budget -= delta;
+            (prediction.simulate)(&mut entity.unwrap().1, &input.unwrap(), delta);
+        } else { warn!("Dropped input from {} for entity {:?}, it has sent more input time than has passed", event.sender, event.network_id); }
+        prediction.processed.insert(event.network_id, event.sequence);
+        prediction.unechoed.insert(event.network_id);
+    });
+
+    if !net.network_tick || !net.is_server() { return }
+    let unechoed: Vec<NetworkID> = prediction.unechoed.drain().collect();
+    unechoed.into_iter().for_each(|network_id| {
+        let entity = entities.iter().find(|(id, _, _)| **id == network_id);
+        if entity.is_none() || !net.connections.contains_key(&network_id.owner) { return }
+        let (_, transform, tracker) = entity.unwrap();
+        net.server_send_targeted(&network_id.owner, NetworkPacketWrapper::InputState {
+            network_id, sequence: prediction.processed[&network_id], transform: NetworkTransform::from_bevy(transform), velocity: tracker.velocity
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    // inputs move along x by their value per second
+    fn walk(transform: &mut Transform, input: &f32, delta: f32) { transform.translation.x += input * delta; }
+
+    fn app() -> (App, Entity) {
+        let mut app = App::new();
+        app
+            .add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .insert_resource(Networking::default())
+            .add_event::<NetworkCallReconcileEvent>()
+            .add_event::<NetworkCallInputEvent>()
+            .add_plugins(NetworkPredictionPlugin::<f32>::new(walk));
+        let entity = app.world.spawn((NetworkID { owner: 1, net_id: 0 }, Transform::from_xyz(100., 0., 0.), NetworkTransformTracker::new(Transform::IDENTITY), NetworkPredicted)).id();
+        app.update();
+        (app, entity)
+    }
+
+    #[test]
+    fn reconciling_replays_only_unacknowledged_inputs() {
+        let (mut app, entity) = app();
+        let network_id = NetworkID { owner: 1, net_id: 0 };
+        let other = NetworkID { owner: 1, net_id: 1 };
+        let mut prediction = app.world.resource_mut::<NetworkPrediction<f32>>();
+        prediction.pending.extend((1 ..= 3).map(|sequence| NetworkInput { network_id, sequence, delta: 0.5, input: 1. }));
+        prediction.pending.push_back(NetworkInput { network_id: other, sequence: 1, delta: 0.5, input: 1. });
+
+        // the server has run the first two inputs and put the entity at 10
+        let transform = NetworkTransform::from_bevy(&Transform::from_xyz(10., 0., 0.));
+        app.world.send_event(NetworkCallReconcileEvent { network_id, sequence: 2, transform, velocity: Vec3::ZERO });
+        app.update();
+
+        let prediction = app.world.resource::<NetworkPrediction<f32>>();
+        let pending: Vec<(NetworkID, u64)> = prediction.pending().iter().map(|input| (input.network_id, input.sequence)).collect();
+        assert_eq!(pending, vec![(network_id, 3), (other, 1)]);
+        assert!((prediction.last_correction - 89.5).abs() < 0.001, "corrected by {}", prediction.last_correction);
+        assert_eq!(app.world.get::<Transform>(entity).unwrap().translation.x, 10.5);
+        assert_eq!(app.world.get::<NetworkTransformTracker>(entity).unwrap().last_transform.translation.x, 10.5);
+    }
+
+    #[test]
+    fn inputs_cannot_add_up_to_more_time_than_passed() {
+        let (mut app, entity) = app();
+        let network_id = NetworkID { owner: 1, net_id: 0 };
+
+        // a player sending ten full length inputs at once only moves as far as the time that passed, which saves up to the max budget
+        (0 .. 10).for_each(|_| app.update());
+        (1 ..= 10).for_each(|sequence| {
+            app.world.send_event(NetworkCallInputEvent { sender: 1, network_id, sequence, delta: 0.25, input: serde_json::to_value(1.).unwrap() });
+        });
+        app.update();
+
+        let moved = app.world.get::<Transform>(entity).unwrap().translation.x - 100.;
+        assert!((moved - MAX_INPUT_BUDGET).abs() < 0.001, "moved {moved}");
+        assert_eq!(app.world.resource::<NetworkPrediction<f32>>().processed[&network_id], 10);
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde_json::Value;
+
+use crate::{*, structs::*};
+
+// plugin that makes the server a relay, passing packets between clients and keeping track of their entities without spawning them
+pub struct NetworkRelayPlugin;
+impl Plugin for NetworkRelayPlugin {
+    fn build(&self, app: &mut App) {
+        app.world.get_resource_or_insert_with(NetworkConfig::default).relay = true;
+        app
+            .init_resource::<NetworkRelay>()
+            .add_systems(Update, (welcome_connections, relay_packets, forget_disconnected, relay_transforms).chain().after(recv_packets).after(handle_disconnects));
+    }
+}
+
+// resource with everything the relay knows about the game, sent to clients when they join
+#[derive(Resource, Debug, Default)]
+pub struct NetworkRelay {
+    pub entities: HashMap<NetworkID, RelayedEntity>,
+    pub state: Option<Value>,
+    // log every relayed packet instead of only entity spawns and removals
+    pub verbose: bool,
+    pub relayed: usize,
+    pub rejected: usize
+}
+
+// An entity a client spawned, with its last transform
+#[derive(Debug, Clone)]
+pub struct RelayedEntity {
+    pub build: Value,
+    pub transform: NetworkTransform,
+    pub velocity: Vec3,
+    // priority built up while waiting to be sent
+    waiting: f32
+}
+
+fn welcome_connections(
+    mut net: ResMut<Networking>,
+    relay: Res<NetworkRelay>,
+    mut connect_events: EventReader<NetworkServerNewConnectionEvent>
+) {
+    connect_events.read().for_each(|event| {
+        info!("Client {} joined, sending {} entities", event.0, relay.entities.len());
+        relay.entities.iter().for_each(|(network_id, entity)| {
+            net.server_send_targeted(&event.0, NetworkPacketWrapper::SpawnEntity { build: entity.build.clone(), transform: entity.transform, network_id: *network_id });
+        });
+        if relay.state.is_some() { net.server_send_targeted(&event.0, NetworkPacketWrapper::SetState { state: relay.state.clone().unwrap() }); }
+    });
+}
+
+fn relay_packets(
+    mut net: ResMut<Networking>,
+    mut relay: ResMut<NetworkRelay>
+) {
+    let packets: Vec<(u8, NetworkPacketWrapper)> = net.relayed.drain(..).collect();
+    packets.into_iter().for_each(|(id, packet)| {
+        if relay.verbose { info!("Packet from {id}: {packet:?}"); }
+
+        // check the packet is allowed and update what the relay knows
+        let allowed = match &packet {
+            NetworkPacketWrapper::Event(_) => true,
+            NetworkPacketWrapper::SpawnEntity { build, transform, network_id } => {
+                let allowed = network_id.owner == id && !relay.entities.contains_key(network_id);
+                if allowed {
+                    info!("Client {id} spawned {network_id:?}");
+                    relay.entities.insert(*network_id, RelayedEntity { build: build.clone(), transform: *transform, velocity: Vec3::ZERO, waiting: 0. });
+                }
+                allowed
+            },
+            NetworkPacketWrapper::RemoveEntity { network_id } => {
+                let allowed = network_id.owner == id && relay.entities.remove(network_id).is_some();
+                if allowed { info!("Client {id} removed {network_id:?}"); }
+                allowed
+            },
+            // transforms are sent by relay_transforms each network tick instead of passed on
+            NetworkPacketWrapper::UpdateEntityTransform { network_id, transform, velocity } => {
+                let entity = if network_id.owner == id { relay.entities.get_mut(network_id) } else { None };
+                if entity.is_none() { relay.rejected += 1; warn!("Rejected packet from {id}: {packet:?}"); return }
+                let entity = entity.unwrap();
+                entity.transform = *transform;
+                entity.velocity = *velocity;
+                relay.relayed += 1;
+                return
+            },
+            NetworkPacketWrapper::SetState { state } => {
+                relay.state = Some(state.clone());
+                true
+            },
+            // only the relay gives out IDs and owners, batches are unpacked when received and inputs need a server that simulates
+            NetworkPacketWrapper::SetNetID(_) | NetworkPacketWrapper::SetOwner { .. } |
+            NetworkPacketWrapper::TransformBatch { .. } | NetworkPacketWrapper::AckTransforms { .. } |
+            NetworkPacketWrapper::Input { .. } | NetworkPacketWrapper::InputState { .. } => false
+        };
+
+        if allowed {
+            relay.relayed += 1;
+            net.server_send_except(&id, packet);
+        } else {
+            relay.rejected += 1;
+            warn!("Rejected packet from {id}: {packet:?}");
+        }
+    });
+}
+
+fn forget_disconnected(
+    mut net: ResMut<Networking>,
+    mut relay: ResMut<NetworkRelay>,
+    config: Res<NetworkConfig>,
+    mut disconnected_events: EventReader<NetworkClientDisconnectedEvent>
+) {
+    disconnected_events.read().for_each(|event| {
+        info!("Client {} left: {}", event.id, event.reason);
+        let owned: Vec<NetworkID> = relay.entities.keys().filter(|network_id| network_id.owner == event.id).copied().collect();
+        if config.owned_entities == OwnedEntityPolicy::Keep && !owned.is_empty() { warn!("Relays cannot keep entities for returning players, despawning the {} owned by {}", owned.len(), event.id); }
+        owned.into_iter().for_each(|network_id| match config.owned_entities {
+            // relays have no way to hand kept entities back, so they are despawned instead of left without an owner forever
+            OwnedEntityPolicy::Despawn | OwnedEntityPolicy::Keep => {
+                relay.entities.remove(&network_id);
+                net.send(NetworkPacketWrapper::RemoveEntity { network_id });
+            },
+            // the relay becomes the owner, with a fresh ID since other players that left may have used the same net_id
+            OwnedEntityPolicy::TransferToServer => {
+                let entity = relay.entities.remove(&network_id).unwrap();
+                let new_id = net.transfer_id(0);
+                relay.entities.insert(new_id, entity);
+                net.send(NetworkPacketWrapper::SetOwner { network_id, new_id });
+            }
+        });
+    });
+}
+
+// send changed transforms to every client but their owner each network tick
+fn relay_transforms(
+    mut net: ResMut<Networking>,
+    mut relay: ResMut<NetworkRelay>,
+    config: Res<NetworkConfig>
+) {
+    if !net.network_tick { return }
+    let candidates = relay.entities.iter_mut().map(|(network_id, entity)| {
+        entity.waiting += 1.;
+        (*network_id, QuantizedTransform::new(&entity.transform, entity.velocity, config.position_precision), entity.waiting)
+    }).collect();
+    let sent = net.replicate(candidates, config.max_transforms_per_tick, config.position_precision);
+    sent.iter().for_each(|network_id| {
+        let entity = relay.entities.get_mut(network_id);
+        if entity.is_some() { entity.unwrap().waiting = 0.; }
+    });
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::{TimePlugin, TimeUpdateStrategy}, utils::HashMap};
+use serde_json::Value;
+
+use crate::{*, event_log::{NetworkLogEntry, NetworkEventLog}, structs::*};
+
+// Re-simulates logged packets in a world of its own, checking the transform updates one player sent against the simulation
+// the setup function adds what the game needs to simulate, like entity builders, synced events and movement systems
+pub struct NetworkReplay {
+    pub app: App,
+    // how far a claimed translation can be from the simulated one before it counts as diverging
+    pub tolerance: f32,
+    // game time that passes each tick
+    pub tick_length: Duration
+}
+
+// A transform update from the checked player that the simulation did not agree with
+#[derive(Debug, Clone)]
+pub struct NetworkDivergence {
+    pub tick: u64,
+    pub sequence: u64,
+    pub network_id: NetworkID,
+    pub claimed: NetworkTransform,
+    // none if the entity does not exist in the simulation
+    pub simulated: Option<NetworkTransform>,
+    pub distance: f32
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetworkReplayReport {
+    pub ticks: u64,
+    // number of transform updates checked
+    pub checked: usize,
+    pub divergences: Vec<NetworkDivergence>
+}
+
+impl NetworkReplayReport {
+    pub fn diverged(&self) -> bool { !self.divergences.is_empty() }
+}
+
+impl NetworkReplay {
+    pub fn new(setup: impl FnOnce(&mut App)) -> Self {
+        // networking is never established here, so nothing the simulation sends leaves this world
+        let mut app = App::new();
+        app
+            .add_plugins(TimePlugin)
+            .add_event::<NetworkCallSpawnEvent>()
+            .add_event::<NetworkCallDespawnEvent>()
+            .add_event::<NetworkCallUpdateTransformEvent>()
+            .add_event::<NetworkStateReceiveEvent>()
+            .add_event::<NetworkCallSetOwnerEvent>()
+            .add_event::<NetworkSetOwnerEvent>()
+            .insert_resource(Networking::default())
+            // transforms apply on the tick they were logged
+            .insert_resource(NetworkConfig { interpolation_delay: 0., ..Default::default() })
+            .add_systems(Update, (update_transforms, set_owners));
+        setup(&mut app);
+        Self { app, tolerance: 0.1, tick_length: Duration::from_secs_f64(1. / 60.) }
+    }
+
+    // replay the logged packets from the first tick to the last, checking the transform updates of the given player
+    // entities that existed before the first tick start where they were last logged
+    pub fn run(&mut self, log: &[NetworkLogEntry], first: u64, last: u64, checked: u8) -> NetworkReplayReport {
+        let mut report = NetworkReplayReport::default();
+        self.app.insert_resource(TimeUpdateStrategy::ManualDuration(self.tick_length));
+        self.app.world.resource_mut::<Networking>().my_id = checked;
+
+        // spawn entities from before the range
+        let start = log.partition_point(|entry| entry.tick < first);
+        prior_entities(&log[.. start]).into_iter().for_each(|(network_id, (build, transform))| {
+            self.app.world.send_event(NetworkCallSpawnEvent { build, transform, network_id });
+        });
+
+        // run each tick with its packets, keeping the checked players transform updates as claims
+        let mut index = start;
+        (first ..= last).for_each(|tick| {
+            let mut claims: Vec<(u64, NetworkID, NetworkTransform)> = Vec::new();
+            while index < log.len() && log[index].tick == tick {
+                let entry = &log[index];
+                index += 1;
+                match entry.packet.clone() {
+                    NetworkPacketWrapper::UpdateEntityTransform { network_id, transform, .. } if entry.sender == checked => claims.push((entry.sequence, network_id, transform)),
+                    packet => self.apply(packet, entry.tick, entry.time)
+                }
+            }
+            self.app.update();
+            report.ticks += 1;
+
+            // compare claims, moving entities to them so each divergence is measured from where the player said it was
+            let mut entities = self.app.world.query::<(&NetworkID, &mut Transform)>();
+            claims.into_iter().for_each(|(sequence, network_id, claimed)| {
+                report.checked += 1;
+                let entity = entities.iter_mut(&mut self.app.world).find(|(id, _)| **id == network_id);
+                let simulated = entity.as_ref().map(|(_, transform)| NetworkTransform::from_bevy(transform));
+                let distance = simulated.map(|simulated| simulated.translation.distance(claimed.translation)).unwrap_or(f32::INFINITY);
+                if distance > self.tolerance { report.divergences.push(NetworkDivergence { tick, sequence, network_id, claimed, simulated, distance }); }
+                if entity.is_some() { *entity.unwrap().1 = claimed.to_bevy(); }
+            });
+        });
+
+        report
+    }
+
+    // replay the ticks of an event log like run, reading entries older than it keeps in memory back from its file
+    pub fn run_log(&mut self, log: &NetworkEventLog, first: u64, last: u64, checked: u8) -> Result<NetworkReplayReport, String> {
+        // everything before the first tick is needed to know which entities already exist
+        let entries = log.range(0, last)?;
+        Ok(self.run(&entries, first, last, checked))
+    }
+
+    fn apply(&mut self, packet: NetworkPacketWrapper, tick: u64, time: f64) {
+        let world = &mut self.app.world;
+        match packet {
+            NetworkPacketWrapper::Event(wrapper) => world.resource_mut::<Networking>().waiting_events.push(wrapper),
+            NetworkPacketWrapper::SpawnEntity { build, transform, network_id } => { world.send_event(NetworkCallSpawnEvent { build, transform, network_id }); },
+            NetworkPacketWrapper::RemoveEntity { network_id } => { world.send_event(NetworkCallDespawnEvent { network_id }); },
+            NetworkPacketWrapper::UpdateEntityTransform { network_id, transform, velocity } => { world.send_event(NetworkCallUpdateTransformEvent { network_id, transform, velocity, tick, time }); },
+            NetworkPacketWrapper::SetState { state } => { world.send_event(NetworkStateReceiveEvent(state)); },
+            NetworkPacketWrapper::SetOwner { network_id, new_id } => { world.send_event(NetworkCallSetOwnerEvent { network_id, new_id }); },
+            NetworkPacketWrapper::SetNetID(_) | NetworkPacketWrapper::TransformBatch { .. } | NetworkPacketWrapper::AckTransforms { .. } |
+            NetworkPacketWrapper::Input { .. } | NetworkPacketWrapper::InputState { .. } => {}
+        }
+    }
+}
+
+// get the entities alive at the end of some logged packets with their last transforms
+fn prior_entities(log: &[NetworkLogEntry]) -> HashMap<NetworkID, (Value, NetworkTransform)> {
+    let mut entities: HashMap<NetworkID, (Value, NetworkTransform)> = HashMap::new();
+    log.iter().for_each(|entry| match &entry.packet {
+        NetworkPacketWrapper::SpawnEntity { build, transform, network_id } => { entities.insert(*network_id, (build.clone(), *transform)); },
+        NetworkPacketWrapper::RemoveEntity { network_id } => { entities.remove(network_id); },
+        NetworkPacketWrapper::UpdateEntityTransform { network_id, transform, .. } => {
+            let entity = entities.get_mut(network_id);
+            if entity.is_some() { entity.unwrap().1 = *transform; }
+        },
+        NetworkPacketWrapper::SetOwner { network_id, new_id } => {
+            let entity = entities.remove(network_id);
+            if entity.is_some() { entities.insert(*new_id, entity.unwrap()); }
+        },
+        _ => {}
+    });
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::EntityCommands;
+    use serde::*;
+
+    use super::*;
+    use crate::builder::GameNetworkEntityBuilder;
+
+    #[derive(Serialize, Deserialize, Event, Debug, Clone)]
+    struct Walker;
+
+    // walkers move one unit along x every tick
+    fn walk(mut walkers: Query<&mut Transform, With<NetworkID>>) {
+        walkers.iter_mut().for_each(|mut transform| transform.translation.x += 1.);
+    }
+
+    fn entry(tick: u64, packet: NetworkPacketWrapper) -> NetworkLogEntry {
+        NetworkLogEntry { sender: 1, tick, time: tick as f64 / 60., sequence: tick, packet }
+    }
+
+    fn claim(tick: u64, network_id: NetworkID, x: f32) -> NetworkLogEntry {
+        let transform = NetworkTransform::from_bevy(&Transform::from_xyz(x, 0., 0.));
+        entry(tick, NetworkPacketWrapper::UpdateEntityTransform { network_id, transform, velocity: Vec3::X })
+    }
+
+    #[test]
+    fn claims_the_simulation_disagrees_with_are_reported() {
+        let mut replay = NetworkReplay::new(|app| {
+            app
+                .add_plugins(GameNetworkEntityBuilder::<Walker>::new(|_: EntityCommands, _, _| {}))
+                .add_systems(Update, walk);
+        });
+
+        // the walker starts moving the tick after it spawns, and the player claims to have jumped ahead on tick 3
+        let network_id = NetworkID { owner: 1, net_id: 0 };
+        let spawn = NetworkPacketWrapper::SpawnEntity { build: serde_json::to_value(Walker).unwrap(), transform: NetworkTransform::from_bevy(&Transform::IDENTITY), network_id };
+        let log = vec![entry(0, spawn), claim(1, network_id, 1.), claim(2, network_id, 2.), claim(3, network_id, 10.), claim(4, network_id, 11.)];
+        let report = replay.run(&log, 0, 4, 1);
+
+        assert_eq!(report.ticks, 5);
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.divergences.len(), 1);
+        let divergence = &report.divergences[0];
+        assert_eq!((divergence.tick, divergence.network_id), (3, network_id));
+        assert!((divergence.distance - 7.).abs() < 0.01, "diverged by {}", divergence.distance);
+    }
+}
@@ -0,0 +1,320 @@
+use std::{collections::VecDeque, f32::consts::FRAC_1_SQRT_2};
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::*;
+
+use crate::{Networking, NetworkPacketWrapper, connection::NetworkConfig, structs::{NetworkID, NetworkTransform}};
+
+// most snapshots kept per connection, older ones are dropped and updates fall back to full values
+const MAX_SNAPSHOTS: usize = 128;
+// bits for each of the three smallest rotation components
+const ROTATION_BITS: u32 = 10;
+
+// optional component that changes how often and how urgently an entity's transform is sent
+#[derive(Component, Debug, Clone)]
+pub struct NetworkReplication {
+    // most updates per second, none sends every network tick
+    pub send_rate: Option<f32>,
+    // when more entities change than fit in a tick, ones with a higher priority wait less
+    pub priority: f32
+}
+
+impl Default for NetworkReplication {
+    fn default() -> Self { Self { send_rate: None, priority: 1. } }
+}
+
+impl NetworkReplication {
+    pub fn new(send_rate: Option<f32>, priority: f32) -> Self { Self { send_rate, priority } }
+}
+
+// A transform and velocity in whole steps of the configured precision, with the rotation packed by compress_rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuantizedTransform {
+    pub translation: [i32; 3],
+    pub rotation: u32,
+    pub scale: [i32; 3],
+    pub velocity: [i32; 3]
+}
+
+impl QuantizedTransform {
+    pub fn new(transform: &NetworkTransform, velocity: Vec3, precision: f32) -> Self {
+        Self {
+            translation: quantize(transform.translation, precision),
+            rotation: compress_rotation(transform.rotation),
+            scale: quantize(transform.scale, precision),
+            velocity: quantize(velocity, precision)
+        }
+    }
+
+    pub fn transform(&self, precision: f32) -> NetworkTransform {
+        NetworkTransform { translation: dequantize(self.translation, precision), rotation: decompress_rotation(self.rotation), scale: dequantize(self.scale, precision) }
+    }
+
+    pub fn velocity(&self, precision: f32) -> Vec3 { dequantize(self.velocity, precision) }
+
+    // get what changed since the baseline, or everything that is not a default without one
+    pub fn delta(&self, network_id: NetworkID, baseline: Option<&QuantizedTransform>, precision: f32) -> TransformDelta {
+        let base = baseline.copied().unwrap_or(Self::new(&NetworkTransform::from_bevy(&Transform::IDENTITY), Vec3::ZERO, precision));
+        let changed = |value: [i32; 3], base: [i32; 3]| if value != base { Some([value[0] - base[0], value[1] - base[1], value[2] - base[2]]) } else { None };
+        TransformDelta {
+            network_id,
+            translation: changed(self.translation, base.translation),
+            rotation: if self.rotation != base.rotation { Some(self.rotation) } else { None },
+            scale: changed(self.scale, base.scale),
+            velocity: changed(self.velocity, base.velocity)
+        }
+    }
+
+    // apply a delta to the baseline it was taken against
+    pub fn apply(baseline: Option<&QuantizedTransform>, delta: &TransformDelta, precision: f32) -> Self {
+        let base = baseline.copied().unwrap_or(Self::new(&NetworkTransform::from_bevy(&Transform::IDENTITY), Vec3::ZERO, precision));
+        let add = |base: [i32; 3], change: Option<[i32; 3]>| change.map(|change| [base[0] + change[0], base[1] + change[1], base[2] + change[2]]).unwrap_or(base);
+        Self {
+            translation: add(base.translation, delta.translation),
+            rotation: delta.rotation.unwrap_or(base.rotation),
+            scale: add(base.scale, delta.scale),
+            velocity: add(base.velocity, delta.velocity)
+        }
+    }
+}
+
+// An entities transform change in a batch, fields that are none did not change
+// none is still written since binary packets are positional
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransformDelta {
+    pub network_id: NetworkID,
+    pub translation: Option<[i32; 3]>,
+    pub rotation: Option<u32>,
+    pub scale: Option<[i32; 3]>,
+    pub velocity: Option<[i32; 3]>
+}
+
+fn quantize(value: Vec3, precision: f32) -> [i32; 3] {
+    [(value.x / precision).round() as i32, (value.y / precision).round() as i32, (value.z / precision).round() as i32]
+}
+
+fn dequantize(value: [i32; 3], precision: f32) -> Vec3 {
+    Vec3::new(value[0] as f32, value[1] as f32, value[2] as f32) * precision
+}
+
+// pack a rotation as the index of its largest component and the other three, which are always between -1/sqrt(2) and 1/sqrt(2)
+pub fn compress_rotation(rotation: Quat) -> u32 {
+    let values = rotation.normalize().to_array();
+    let largest = (0 .. 4).max_by(|a, b| values[*a].abs().total_cmp(&values[*b].abs())).unwrap();
+
+    // flip so the largest is positive, both signs are the same rotation
+    let sign = if values[largest] < 0. { -1. } else { 1. };
+    let max = ((1 << ROTATION_BITS) - 1) as f32;
+    (0 .. 4).filter(|index| *index != largest).fold(largest as u32, |packed, index| {
+        let value = ((values[index] * sign + FRAC_1_SQRT_2) / (2. * FRAC_1_SQRT_2) * max).round().clamp(0., max) as u32;
+        (packed << ROTATION_BITS) | value
+    })
+}
+
+pub fn decompress_rotation(packed: u32) -> Quat {
+    let max = ((1 << ROTATION_BITS) - 1) as f32;
+    let largest = (packed >> (ROTATION_BITS * 3)) as usize & 3;
+    let mut values = [0.; 4];
+    let mut shift = ROTATION_BITS * 3;
+    (0 .. 4).filter(|index| *index != largest).for_each(|index| {
+        shift -= ROTATION_BITS;
+        let value = ((packed >> shift) & ((1 << ROTATION_BITS) - 1)) as f32;
+        values[index] = value / max * 2. * FRAC_1_SQRT_2 - FRAC_1_SQRT_2;
+    });
+    values[largest] = (1. - values.iter().map(|value| value * value).sum::<f32>()).max(0.).sqrt();
+    Quat::from_array(values).normalize()
+}
+
+// transforms sent to and received from one connection, by batch tick, so batches only carry changes since the last acknowledged one
+#[derive(Debug, Default)]
+pub(crate) struct ReplicationLink {
+    sent: VecDeque<(u64, HashMap<NetworkID, QuantizedTransform>)>,
+    acked: Option<u64>,
+    received: VecDeque<(u64, HashMap<NetworkID, QuantizedTransform>)>,
+    // last received batch, waiting to be acknowledged
+    unacked: Option<u64>
+}
+
+impl ReplicationLink {
+    // get the last state sent for an entity
+    pub(crate) fn latest(&self, network_id: &NetworkID) -> Option<&QuantizedTransform> {
+        self.sent.back().and_then(|(_, snapshot)| snapshot.get(network_id))
+    }
+
+    // make a batch against the last acknowledged snapshot, remembering what was sent
+    pub(crate) fn encode(&mut self, tick: u64, updates: Vec<(NetworkID, QuantizedTransform)>, precision: f32) -> NetworkPacketWrapper {
+        let baseline = self.acked.and_then(|acked| self.sent.iter().find(|(tick, _)| *tick == acked));
+        let deltas = updates.iter().map(|(network_id, state)| state.delta(*network_id, baseline.and_then(|(_, snapshot)| snapshot.get(network_id)), precision)).collect();
+        let baseline = baseline.map(|(tick, _)| *tick);
+
+        let mut snapshot = self.sent.back().map(|(_, snapshot)| snapshot.clone()).unwrap_or_default();
+        snapshot.extend(updates);
+        self.sent.push_back((tick, snapshot));
+        if self.sent.len() > MAX_SNAPSHOTS { self.sent.pop_front(); }
+        NetworkPacketWrapper::TransformBatch { tick, baseline, precision, updates: deltas }
+    }
+
+    pub(crate) fn acknowledge(&mut self, tick: u64) {
+        if self.acked.is_some_and(|acked| acked >= tick) { return }
+        self.acked = Some(tick);
+        self.sent.retain(|(sent, _)| *sent >= tick);
+    }
+
+    // turn a received batch into transform updates, keeping it as a baseline for later batches
+    pub(crate) fn receive(&mut self, tick: u64, baseline: Option<u64>, precision: f32, updates: Vec<TransformDelta>) -> Vec<NetworkPacketWrapper> {
+        let base = if baseline.is_some() { self.received.iter().find(|(received, _)| Some(*received) == baseline) } else { None };
+        if baseline.is_some() && base.is_none() { error!("Missing baseline {} for transform batch {tick}", baseline.unwrap()); return Vec::new() }
+
+        let mut snapshot = self.received.back().map(|(_, snapshot)| snapshot.clone()).unwrap_or_default();
+        let packets = updates.iter().map(|delta| {
+            let state = QuantizedTransform::apply(base.and_then(|(_, base)| base.get(&delta.network_id)), delta, precision);
+            snapshot.insert(delta.network_id, state);
+            NetworkPacketWrapper::UpdateEntityTransform { network_id: delta.network_id, transform: state.transform(precision), velocity: state.velocity(precision) }
+        }).collect();
+
+        // older snapshots are never used again once the sender has moved its baseline past them
+        if baseline.is_some() { self.received.retain(|(received, _)| *received >= baseline.unwrap()); }
+        self.received.push_back((tick, snapshot));
+        if self.received.len() > MAX_SNAPSHOTS { self.received.pop_front(); }
+        self.unacked = Some(tick);
+        packets
+    }
+}
+
+impl Networking {
+    // send a batch to each connection with the candidates that changed for it, highest waiting first, returning the ones sent to anyone
+    // candidates are not sent back to the connection that owns them
+    pub(crate) fn replicate(&mut self, mut candidates: Vec<(NetworkID, QuantizedTransform, f32)>, budget: usize, precision: f32) -> Vec<NetworkID> {
+        if !self.established() { return Vec::new() }
+        candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+        self.batch_tick += 1;
+        let tick = self.batch_tick;
+
+        let peers: Vec<u8> = if self.is_server() { self.connections.keys().copied().collect() } else { vec![0] };
+        let mut sent: Vec<NetworkID> = Vec::new();
+        peers.into_iter().for_each(|peer| {
+            let link = self.links.entry(peer).or_default();
+            let updates: Vec<(NetworkID, QuantizedTransform)> = candidates.iter()
+                .filter(|(network_id, state, _)| (peer == 0 || network_id.owner != peer) && link.latest(network_id) != Some(state))
+                .take(budget)
+                .map(|(network_id, state, _)| (*network_id, *state))
+                .collect();
+            if updates.is_empty() { return }
+            updates.iter().for_each(|(network_id, _)| if !sent.contains(network_id) { sent.push(*network_id); });
+
+            let packet = link.encode(tick, updates, precision);
+            if self.is_server() { self.server_send_targeted(&peer, packet); } else { self.send(packet); }
+        });
+        sent
+    }
+}
+
+// acknowledge received batches once per network tick
+pub(crate) fn send_transform_acks(mut net: ResMut<Networking>) {
+    if !net.network_tick { return }
+    let acks: Vec<(u8, u64)> = net.links.iter_mut().filter_map(|(peer, link)| link.unacked.take().map(|tick| (*peer, tick))).collect();
+    acks.into_iter().for_each(|(peer, tick)| {
+        if net.is_server() { net.server_send_targeted(&peer, NetworkPacketWrapper::AckTransforms { tick }); }
+        else { net.send(NetworkPacketWrapper::AckTransforms { tick }); }
+    });
+}
+
+// count down to the next network tick, which is when transforms and acknowledgements are sent
+pub(crate) fn advance_network_tick(
+    mut net: ResMut<Networking>,
+    config: Res<NetworkConfig>,
+    time: Res<Time>
+) {
+    let interval = 1. / config.network_tick_rate.max(0.001);
+    net.network_tick_timer += time.delta_seconds();
+    net.network_tick = net.network_tick_timer >= interval;
+    if net.network_tick { net.network_tick_timer = (net.network_tick_timer - interval).min(interval); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NetworkStampedPacket, wire::NetworkWireFormat};
+
+    const PRECISION: f32 = 0.001;
+
+    fn state(translation: Vec3) -> QuantizedTransform {
+        QuantizedTransform::new(&NetworkTransform { translation, rotation: Quat::from_rotation_y(0.3), scale: Vec3::ONE }, Vec3::new(1.5, 0., -0.25), PRECISION)
+    }
+
+    fn binary_size(packet: NetworkPacketWrapper) -> usize {
+        NetworkWireFormat::Binary.encode(&NetworkStampedPacket { tick: 1200, time: 20., sequence: 1, packet }).len()
+    }
+
+    #[test]
+    fn rotations_survive_compression() {
+        (0 .. 200).for_each(|index| {
+            let index = index as f32;
+            let rotation = Quat::from_euler(EulerRot::YXZ, index * 0.31, index * 0.17 - 1.5, index * 0.05).normalize();
+            let error = rotation.angle_between(decompress_rotation(compress_rotation(rotation)));
+            assert!(error < 0.005, "{rotation} came back {error} radians off");
+        });
+    }
+
+    #[test]
+    fn deltas_apply_back_to_the_state() {
+        let base = state(Vec3::new(1., 2., 3.));
+        let moved = state(Vec3::new(1.05, 2., 3.));
+        let network_id = NetworkID { owner: 1, net_id: 0 };
+
+        let delta = moved.delta(network_id, Some(&base), PRECISION);
+        assert!(delta.rotation.is_none() && delta.scale.is_none() && delta.velocity.is_none());
+        assert_eq!(QuantizedTransform::apply(Some(&base), &delta, PRECISION), moved);
+        assert_eq!(QuantizedTransform::apply(None, &moved.delta(network_id, None, PRECISION), PRECISION), moved);
+    }
+
+    #[test]
+    fn delta_batches_are_smaller_than_separate_updates() {
+        let entities: Vec<(NetworkID, Vec3)> = (0 .. 32).map(|index| (NetworkID { owner: 1, net_id: index }, Vec3::new(index as f32 * 1.37, 0.5, -(index as f32) * 0.73))).collect();
+        let separate: usize = entities.iter().map(|(network_id, translation)| binary_size(NetworkPacketWrapper::UpdateEntityTransform {
+            network_id: *network_id, transform: state(*translation).transform(PRECISION), velocity: state(*translation).velocity(PRECISION)
+        })).sum();
+
+        // a tick later against the first batch, after moving by the velocity
+        let mut link = ReplicationLink::default();
+        let full = link.encode(1, entities.iter().map(|(network_id, translation)| (*network_id, state(*translation))).collect(), PRECISION);
+        link.acknowledge(1);
+        let delta = link.encode(2, entities.iter().map(|(network_id, translation)| (*network_id, state(*translation + Vec3::new(0.05, 0., -0.008)))).collect(), PRECISION);
+
+        let (full, delta) = (binary_size(full), binary_size(delta));
+        assert!(full < separate, "full batch is {full} bytes, separate updates are {separate}");
+        assert!(delta < full, "delta batch is {delta} bytes, full batch is {full}");
+    }
+
+    #[test]
+    fn batches_use_the_acknowledged_baseline() {
+        let (mut sender, mut receiver) = (ReplicationLink::default(), ReplicationLink::default());
+        let network_id = NetworkID { owner: 1, net_id: 0 };
+        let unbatch = |packet: NetworkPacketWrapper, receiver: &mut ReplicationLink| match packet {
+            NetworkPacketWrapper::TransformBatch { tick, baseline, precision, updates } => (baseline, receiver.receive(tick, baseline, precision, updates)),
+            packet => panic!("expected a batch, got {packet:?}")
+        };
+
+        // nothing acknowledged yet, so the first two batches are sent in full
+        let (baseline, _) = unbatch(sender.encode(1, vec![(network_id, state(Vec3::X))], PRECISION), &mut receiver);
+        assert_eq!(baseline, None);
+        assert_eq!(receiver.unacked.take(), Some(1));
+        let (baseline, _) = unbatch(sender.encode(2, vec![(network_id, state(Vec3::Y))], PRECISION), &mut receiver);
+        assert_eq!(baseline, None);
+
+        // once acknowledged, batches only carry changes against it and still arrive as the full transform
+        sender.acknowledge(1);
+        let (baseline, updates) = unbatch(sender.encode(3, vec![(network_id, state(Vec3::Z))], PRECISION), &mut receiver);
+        assert_eq!(baseline, Some(1));
+        let NetworkPacketWrapper::UpdateEntityTransform { transform, .. } = &updates[0] else { panic!("expected a transform update") };
+        assert!(transform.translation.distance(Vec3::Z) <= PRECISION);
+    }
+
+    #[test]
+    fn missing_baselines_are_skipped() {
+        let mut receiver = ReplicationLink::default();
+        let delta = state(Vec3::X).delta(NetworkID { owner: 1, net_id: 0 }, Some(&state(Vec3::ZERO)), PRECISION);
+        assert!(receiver.receive(5, Some(4), PRECISION, vec![delta]).is_empty());
+        assert_eq!(receiver.unacked, None);
+    }
+}
@@ -1,9 +1,11 @@
-use std::fmt::Debug;
+use std::{collections::VecDeque, fmt::Debug};
 
 use bevy::prelude::*;
 use serde::*;
 use serde_json::Value;
 
+use crate::interpolation::NetworkSnapshot;
+
 // a network ID
 #[derive(Component, Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize, Default)]
 pub struct NetworkID {
@@ -14,7 +16,18 @@ pub struct NetworkID {
 #[derive(Component)]
 pub struct NetworkTransformTracker {
     pub last_transform: Transform,
-    pub velocity: Vec3
+    pub velocity: Vec3,
+    // priority built up while waiting to be sent and when it was last sent
+    pub(crate) waiting: f32,
+    pub(crate) last_sent: f32,
+    // received transforms of remote entities, shown a little in the past so there is always one to move towards
+    pub(crate) snapshots: VecDeque<NetworkSnapshot>,
+    // local time minus the senders time, to put snapshots on the local clock
+    pub(crate) clock_offset: Option<f64>
+}
+
+impl NetworkTransformTracker {
+    pub fn new(transform: Transform) -> Self { Self { last_transform: transform, velocity: Vec3::ZERO, waiting: 0., last_sent: 0., snapshots: VecDeque::new(), clock_offset: None } }
 }
 
 // custom transform to represent bevy transform with serialize and deserialize
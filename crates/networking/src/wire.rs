@@ -0,0 +1,131 @@
+use tungstenite::Message;
+
+use crate::NetworkStampedPacket;
+
+// websocket subprotocols offered by clients and chosen by the server during the handshake
+const BINARY_PROTOCOL: &str = "nebulous.msgpack";
+const JSON_PROTOCOL: &str = "nebulous.json";
+
+// How packets are encoded, binary is smaller while json can be read when debugging
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkWireFormat {
+    #[default]
+    Binary,
+    Json
+}
+
+impl NetworkWireFormat {
+    pub fn protocol(&self) -> &'static str {
+        match self {
+            NetworkWireFormat::Binary => BINARY_PROTOCOL,
+            NetworkWireFormat::Json => JSON_PROTOCOL
+        }
+    }
+
+    pub fn from_protocol(protocol: &str) -> Option<Self> {
+        match protocol.trim() {
+            BINARY_PROTOCOL => Some(NetworkWireFormat::Binary),
+            JSON_PROTOCOL => Some(NetworkWireFormat::Json),
+            _ => None
+        }
+    }
+
+    // the protocols a client offers, this format first
+    pub(crate) fn offer(&self) -> String {
+        let other = match self { NetworkWireFormat::Binary => NetworkWireFormat::Json, NetworkWireFormat::Json => NetworkWireFormat::Binary };
+        format!("{}, {}", self.protocol(), other.protocol())
+    }
+
+    // pick a format from what a client offered, servers using json always pick it and clients that offer nothing get json
+    pub(crate) fn choose(&self, offer: Option<&str>) -> Self {
+        if *self == NetworkWireFormat::Json { return NetworkWireFormat::Json }
+        offer.and_then(|offer| offer.split(',').find_map(Self::from_protocol)).unwrap_or(NetworkWireFormat::Json)
+    }
+
+    pub fn encode(&self, packet: &NetworkStampedPacket) -> Message {
+        match self {
+            NetworkWireFormat::Binary => Message::Binary(rmp_serde::to_vec(packet).unwrap()),
+            NetworkWireFormat::Json => Message::Text(serde_json::to_string(packet).unwrap())
+        }
+    }
+
+    // decode a text or binary message, the message type tells which format it uses
+    pub fn decode(message: &Message) -> Option<Result<NetworkStampedPacket, String>> {
+        match message {
+            Message::Text(text) => Some(serde_json::from_str(text).map_err(|error| error.to_string())),
+            Message::Binary(bytes) => Some(rmp_serde::from_slice(bytes).map_err(|error| error.to_string())),
+            _ => None
+        }
+    }
+}
+
+// a packet encoded at most once per format, for sending to connections that use different formats
+pub(crate) struct EncodedPacket {
+    packet: NetworkStampedPacket,
+    binary: Option<Message>,
+    json: Option<Message>
+}
+
+impl EncodedPacket {
+    pub(crate) fn new(packet: NetworkStampedPacket) -> Self { Self { packet, binary: None, json: None } }
+
+    pub(crate) fn get(&mut self, format: NetworkWireFormat) -> Message {
+        let message = match format { NetworkWireFormat::Binary => &mut self.binary, NetworkWireFormat::Json => &mut self.json };
+        message.get_or_insert_with(|| format.encode(&self.packet)).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{NetworkPacketWrapper, events::NetworkEventWrapper, structs::*};
+
+    // a frame with a transform update for each entity, an event and a spawn
+    fn frame(entities: u64) -> Vec<NetworkStampedPacket> {
+        let mut packets: Vec<NetworkPacketWrapper> = (0 .. entities).map(|index| {
+            let transform = Transform::from_xyz(index as f32 * 1.37, 0.5, -(index as f32) * 0.73).with_rotation(Quat::from_rotation_y(index as f32 * 0.1));
+            NetworkPacketWrapper::UpdateEntityTransform { network_id: NetworkID { owner: 1, net_id: index }, transform: NetworkTransform::from_bevy(&transform), velocity: Vec3::new(1.5, 0., -0.25) }
+        }).collect();
+        packets.push(NetworkPacketWrapper::Event(NetworkEventWrapper {
+            event: "game::events::JumpEvent".into(),
+            value: json!({ "player": 1, "height": 2.5, "received": false }),
+            from_addr: None
+        }));
+        packets.push(NetworkPacketWrapper::SpawnEntity {
+            build: json!({ "Player": { "name": "player 1", "color": [0.2, 0.4, 0.8] } }),
+            transform: NetworkTransform::from_bevy(&Transform::from_xyz(4., 0., 2.)),
+            network_id: NetworkID { owner: 1, net_id: entities }
+        });
+        packets.into_iter().enumerate().map(|(index, packet)| NetworkStampedPacket { tick: 1200, time: 20., sequence: index as u64 + 1, packet }).collect()
+    }
+
+    fn frame_size(format: NetworkWireFormat, packets: &[NetworkStampedPacket]) -> usize {
+        packets.iter().map(|packet| format.encode(packet).len()).sum()
+    }
+
+    #[test]
+    fn packets_decode_to_what_was_sent() {
+        [NetworkWireFormat::Json, NetworkWireFormat::Binary].into_iter().for_each(|format| frame(4).iter().for_each(|packet| {
+            let decoded = NetworkWireFormat::decode(&format.encode(packet)).unwrap().unwrap();
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(packet).unwrap(), "{format:?} changed {packet:?}");
+        }));
+    }
+
+    #[test]
+    fn binary_frames_are_smaller_than_json() {
+        let packets = frame(32);
+        let (json, binary) = (frame_size(NetworkWireFormat::Json, &packets), frame_size(NetworkWireFormat::Binary, &packets));
+        assert!(binary < json, "binary frame is {binary} bytes, json is {json}");
+    }
+
+    #[test]
+    fn formats_are_chosen_from_the_offer() {
+        assert_eq!(NetworkWireFormat::Binary.choose(Some(&NetworkWireFormat::Binary.offer())), NetworkWireFormat::Binary);
+        assert_eq!(NetworkWireFormat::Binary.choose(Some(&NetworkWireFormat::Json.offer())), NetworkWireFormat::Json);
+        assert_eq!(NetworkWireFormat::Json.choose(Some(&NetworkWireFormat::Binary.offer())), NetworkWireFormat::Json);
+        assert_eq!(NetworkWireFormat::Binary.choose(None), NetworkWireFormat::Json);
+    }
+}
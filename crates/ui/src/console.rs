@@ -0,0 +1,214 @@
+use bevy::{prelude::*, log::Level};
+use nebulousengine_utils::console::{ConsoleCommands, ConsoleLog};
+
+use crate::{node::UINode, text_area::{UITextArea, UITextAreaSelected, do_typing}, UIID};
+
+const CONSOLE_ID: &str = "console";
+const CONSOLE_LOG_ID: &str = "console_log";
+const CONSOLE_INPUT_ID: &str = "console_input";
+
+// resource that controls the developer console overlay
+#[derive(Resource, Debug, Clone)]
+pub struct UIConsole {
+    pub toggle_key: KeyCode,
+    pub visible_lines: usize,
+    pub max_history: usize,
+    pub open: bool,
+    pub history: Vec<String>,
+    history_index: Option<usize>,
+    scroll: usize,
+    last_total: usize,
+    built: bool
+}
+
+impl Default for UIConsole {
+    fn default() -> Self {
+        Self {
+            toggle_key: KeyCode::Grave,
+            visible_lines: 12,
+            max_history: 50,
+            open: false,
+            history: Vec::new(),
+            history_index: None,
+            scroll: 0,
+            last_total: usize::MAX,
+            built: false
+        }
+    }
+}
+
+impl UIConsole {
+    pub fn toggle(&mut self) { self.open = !self.open; }
+}
+
+// plugin for the developer console overlay
+pub struct UIConsolePlugin;
+impl Plugin for UIConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.world.get_resource_or_insert_with(ConsoleCommands::default);
+        app.world.get_resource_or_insert_with(ConsoleLog::default);
+        app
+            .init_resource::<UIConsole>()
+            .add_systems(Update, (build_console, toggle_console, update_console_input.after(do_typing), update_console_log).chain());
+    }
+}
+
+fn build_console(
+    mut console: ResMut<UIConsole>,
+    mut ui: ResMut<UINode>
+) {
+    if console.built { return }
+    console.built = true;
+
+    // add console panel to the root ui, hidden until opened
+    ui.panel().id(CONSOLE_ID)
+        .display(Display::None)
+        .position_type(PositionType::Absolute)
+        .top(Val::Px(0.0)).left(Val::Px(0.0))
+        .width(Val::Percent(100.0)).height(Val::Percent(40.0))
+        .flex_direction(FlexDirection::Column)
+        .bg(Color::rgba(0.0, 0.0, 0.0, 0.8))
+        .children(|ui| {
+            // log lines, wrapped in a panel so they keep their place when re-rendered
+            ui.panel()
+                .width(Val::Percent(100.0))
+                .flex_grow(1.0)
+                .overflow(Overflow::clip())
+                .flex_direction(FlexDirection::Column)
+                .justify_content(JustifyContent::FlexEnd)
+                .children(|ui| { ui.text("").id(CONSOLE_LOG_ID); });
+
+            // command line
+            ui.text_area(Color::WHITE, 20.0).id(CONSOLE_INPUT_ID)
+                .ghost_text("Enter command...")
+                .width(Val::Percent(100.0))
+                .height(Val::Px(28.0))
+                .bg(Color::rgba(0.1, 0.1, 0.1, 1.0));
+        });
+}
+
+fn toggle_console(
+    mut console: ResMut<UIConsole>,
+    mut ui: ResMut<UINode>,
+    keys: Res<Input<KeyCode>>
+) {
+    // toggle on key press
+    if keys.just_pressed(console.toggle_key) { console.toggle(); }
+    if !console.is_changed() { return }
+
+    // update panel visibility
+    let panel = ui.get_mut(CONSOLE_ID);
+    let panel = if panel.is_some() { panel.unwrap() } else { return };
+    let display = if console.open { Display::Flex } else { Display::None };
+    if panel.style.display != display { panel.display(display); }
+}
+
+fn update_console_input(
+    mut commands: Commands,
+    mut console: ResMut<UIConsole>,
+    mut console_commands: ResMut<ConsoleCommands>,
+    log: Res<ConsoleLog>,
+    keys: Res<Input<KeyCode>>,
+    mut areas: Query<(Entity, &mut UITextArea, &UIID, Option<&UITextAreaSelected>)>
+) {
+    // find command line
+    let open = console.open;
+    let mut input = None;
+    for (entity, area, id, selected) in areas.iter_mut() {
+        if id.0 == CONSOLE_INPUT_ID { input = Some((entity, area, selected.is_some())); }
+        // deselect other text areas while the console is open
+        else if open && selected.is_some() { commands.entity(entity).remove::<UITextAreaSelected>(); }
+    }
+    let (entity, mut area, selected) = if input.is_some() { input.unwrap() } else { return };
+
+    // keep the command line selected only while open
+    if open != selected {
+        if open { commands.entity(entity).insert(UITextAreaSelected); }
+        else { commands.entity(entity).remove::<UITextAreaSelected>(); }
+    }
+    if !open { return }
+
+    // remove characters typed by the toggle and completion keys
+    let toggle_char = if console.toggle_key == KeyCode::Grave { Some('`') } else { None };
+    if area.current.contains('\t') || toggle_char.is_some_and(|c| area.current.contains(c)) {
+        area.current = area.current.chars().filter(|c| *c != '\t' && Some(*c) != toggle_char).collect();
+        area.cursor_position = area.current.len();
+    }
+
+    // submit command
+    if keys.just_pressed(KeyCode::Return) || keys.just_pressed(KeyCode::NumpadEnter) {
+        let line = area.current.trim().to_string();
+        area.current.clear();
+        area.cursor_position = 0;
+        console.history_index = None;
+        console.scroll = 0;
+        if line.is_empty() { return }
+
+        // save to history and run
+        if console.history.last() != Some(&line) { console.history.push(line.clone()); }
+        while console.history.len() > console.max_history { console.history.remove(0); }
+        console_commands.run(line);
+    }
+
+    // move through history
+    if keys.just_pressed(KeyCode::Up) && !console.history.is_empty() {
+        let index = match console.history_index { Some(index) => index.saturating_sub(1), None => console.history.len() - 1 };
+        console.history_index = Some(index);
+        area.current = console.history[index].clone();
+        area.cursor_position = area.current.len();
+    }
+    if keys.just_pressed(KeyCode::Down) && console.history_index.is_some() {
+        let index = console.history_index.unwrap() + 1;
+        console.history_index = if index < console.history.len() { Some(index) } else { None };
+        area.current = if index < console.history.len() { console.history[index].clone() } else { String::new() };
+        area.cursor_position = area.current.len();
+    }
+
+    // tab completion, completes fully if only one option, otherwise completes the common part and prints the options
+    if keys.just_pressed(KeyCode::Tab) {
+        let options = console_commands.complete(&area.current);
+        if options.len() == 1 {
+            area.current = format!("{} ", options[0]);
+        } else if options.len() > 1 {
+            let common = options.iter().skip(1).fold(options[0].clone(), |common, option| {
+                common.chars().zip(option.chars()).take_while(|(a, b)| a == b).map(|(a, _)| a).collect()
+            });
+            if common.len() > area.current.len() { area.current = common; }
+            log.push(Level::INFO, options.join("  "));
+        }
+        area.cursor_position = area.current.len();
+    }
+
+    // scroll log
+    if keys.just_pressed(KeyCode::PageUp) { console.scroll += console.visible_lines / 2; }
+    if keys.just_pressed(KeyCode::PageDown) { console.scroll = console.scroll.saturating_sub(console.visible_lines / 2); }
+}
+
+fn update_console_log(
+    mut console: ResMut<UIConsole>,
+    mut ui: ResMut<UINode>,
+    log: Res<ConsoleLog>
+) {
+    // only update when the log or scroll changed
+    let total = log.total();
+    if total == console.last_total && !console.is_changed() { return }
+    console.last_total = total;
+
+    // get visible lines, newest at the bottom
+    let lines = log.lines();
+    let max_scroll = lines.len().saturating_sub(console.visible_lines);
+    if console.scroll > max_scroll { console.scroll = max_scroll; }
+    let end = lines.len() - console.scroll;
+    let start = end.saturating_sub(console.visible_lines);
+    let text = lines[start .. end].iter().map(|line| {
+        match line.level {
+            Level::ERROR => format!("[ERROR] {}", line.text),
+            Level::WARN => format!("[WARN] {}", line.text),
+            _ => line.text.clone()
+        }
+    }).collect::<Vec<String>>().join("\n");
+
+    // update text
+    let node = ui.get_mut(CONSOLE_LOG_ID);
+    if node.is_some() { node.unwrap().set_text(text); }
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+use nebulousengine_input::contexts::{InputClaims, InputConsume};
+
+use crate::text_area::UITextAreaSelected;
+
+const TEXT_ENTRY_CLAIM: &str = "ui_text_entry";
+
+// plugin that claims the keyboard from all inputs while a text area is selected, so typing does not move the player
+pub struct UIInputClaimsPlugin;
+impl Plugin for UIInputClaimsPlugin {
+    fn build(&self, app: &mut App) {
+        app.world.get_resource_or_insert_with(InputClaims::default);
+        app.add_systems(Update, claim_keyboard);
+    }
+}
+
+fn claim_keyboard(
+    mut claims: ResMut<InputClaims>,
+    selected: Query<(), With<UITextAreaSelected>>
+) {
+    let typing = !selected.is_empty();
+    if typing == claims.is_claimed(TEXT_ENTRY_CLAIM) { return }
+    if typing { claims.claim(TEXT_ENTRY_CLAIM, InputConsume::Keyboard); }
+    else { claims.release(TEXT_ENTRY_CLAIM); }
+}
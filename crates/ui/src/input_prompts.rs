@@ -0,0 +1,93 @@
+use bevy::{prelude::*, utils::HashMap};
+use nebulousengine_input::structs::{Inputs, InputDescription};
+
+use crate::node::UINode;
+
+// plugin that keeps prompt text and images up to date with the device the player last used
+pub struct UIInputPromptsPlugin;
+impl Plugin for UIInputPromptsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<UIInputPrompts>()
+            .add_systems(Update, update_prompts);
+    }
+}
+
+// A ui node that shows the bindings of an action
+#[derive(Debug, Clone)]
+pub struct UIInputPrompt {
+    pub action: String,
+    // text where {} is replaced with the prompt (ex. "Press {} to interact")
+    pub template: String,
+    // if set, the node shows an image at this path where {} is replaced with the glyph id (ex. "prompts/{}.png")
+    pub image_path: Option<String>,
+    // inputs to read bindings from, the first inputs found if none
+    pub inputs: Option<Entity>
+}
+
+impl UIInputPrompt {
+    pub fn for_inputs(&mut self, entity: Entity) -> &mut Self {
+        self.inputs = Some(entity);
+        self
+    }
+}
+
+// resource of all prompts by ui node id
+#[derive(Resource, Debug, Default, Clone)]
+pub struct UIInputPrompts {
+    pub prompts: HashMap<String, UIInputPrompt>,
+    shown: HashMap<String, String>
+}
+
+impl UIInputPrompts {
+    // show the prompt for an action as text in the node with the given id
+    pub fn text(&mut self, id: impl Into<String>, action: impl Into<String>, template: impl Into<String>) -> &mut UIInputPrompt {
+        self.insert(id.into(), UIInputPrompt { action: action.into(), template: template.into(), image_path: None, inputs: None })
+    }
+
+    // show the glyph for an action as an image in the node with the given id
+    pub fn image(&mut self, id: impl Into<String>, action: impl Into<String>, path: impl Into<String>) -> &mut UIInputPrompt {
+        self.insert(id.into(), UIInputPrompt { action: action.into(), template: String::new(), image_path: Some(path.into()), inputs: None })
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.prompts.remove(id);
+        self.shown.remove(id);
+    }
+
+    fn insert(&mut self, id: String, prompt: UIInputPrompt) -> &mut UIInputPrompt {
+        self.shown.remove(&id);
+        self.prompts.insert(id.clone(), prompt);
+        self.prompts.get_mut(&id).unwrap()
+    }
+}
+
+fn update_prompts(
+    mut prompts: ResMut<UIInputPrompts>,
+    mut ui: ResMut<UINode>,
+    inputs: Query<&Inputs>,
+    descriptions: Res<Assets<InputDescription>>,
+    asset_server: Res<AssetServer>
+) {
+    let prompts = prompts.as_mut();
+    prompts.prompts.iter().for_each(|(id, prompt)| {
+        // get the prompt for the current device
+        let source = if prompt.inputs.is_some() { inputs.get(prompt.inputs.unwrap()).ok() } else { inputs.iter().next() };
+        let input_prompt = source.and_then(|inputs| inputs.prompt(&prompt.action, &descriptions));
+
+        // get what should be shown, stopping if it is already shown
+        let shown = match (&prompt.image_path, &input_prompt) {
+            (Some(path), Some(input_prompt)) => path.replace("{}", input_prompt.glyphs.first().map(|glyph| glyph.as_str()).unwrap_or("")),
+            (Some(_), None) => String::new(),
+            (None, _) => prompt.template.replace("{}", &input_prompt.map(|input_prompt| input_prompt.to_string()).unwrap_or("[?]".into()))
+        };
+        if prompts.shown.get(id) == Some(&shown) { return }
+
+        // update node, trying again next frame if it does not exist yet
+        let node = ui.get_mut(id);
+        let node = if node.is_some() { node.unwrap() } else { return };
+        if prompt.image_path.is_some() { if !shown.is_empty() { node.image(asset_server.load(shown.clone())); } }
+        else { node.set_text(shown.clone()); }
+        prompts.shown.insert(id.clone(), shown);
+    });
+}
@@ -1,14 +1,26 @@
 use bevy::{prelude::*, input::mouse::{MouseWheel, MouseScrollUnit}, window::PrimaryWindow};
 use camera::UICameraPlugin;
+use console::UIConsolePlugin;
 use events::*;
 use node::UINode;
 use text_area::UITextAreaPlugin;
+use toasts::UIToastsPlugin;
 use ui::{render_ui, UI};
 
 pub mod camera;
+pub mod console;
 pub mod events;
+#[cfg(feature = "input")]
+pub mod input_claims;
+#[cfg(feature = "input")]
+pub mod input_prompts;
 pub mod node;
 pub mod text_area;
+pub mod toasts;
+#[cfg(feature = "input")]
+pub mod touch_controls;
+#[cfg(feature = "networking")]
+pub mod network_toasts;
 pub mod ui;
 
 #[derive(Component, Default, Debug, Clone, Copy)]
@@ -38,9 +50,13 @@ pub struct ConfigurableUIPlugin;
 impl Plugin for ConfigurableUIPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_plugins((UIEventsPlugin, UITextAreaPlugin, UICameraPlugin))
+            .add_plugins((UIEventsPlugin, UITextAreaPlugin, UICameraPlugin, UIConsolePlugin, UIToastsPlugin))
             .init_resource::<UINode>()
             .add_systems(Update, (update_ui, update_hover_press, update_scroll, update_sliders));
+
+        // typing in text areas hides the keyboard from inputs, prompts follow the last used device and touch controls are drawn
+        #[cfg(feature = "input")]
+        app.add_plugins((input_claims::UIInputClaimsPlugin, input_prompts::UIInputPromptsPlugin, touch_controls::UITouchControlsPlugin));
     }
 }
 
@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use nebulousengine_networking::{NetworkServerNewConnectionEvent, connection::*};
+
+use crate::toasts::UIToasts;
+
+// optional plugin that shows toasts for network events
+pub struct UINetworkToastsPlugin;
+impl Plugin for UINetworkToastsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, toast_network_events);
+    }
+}
+
+fn toast_network_events(
+    mut toasts: ResMut<UIToasts>,
+    mut connections: EventReader<NetworkServerNewConnectionEvent>,
+    mut changes: EventReader<NetworkConnectionChangedEvent>,
+    mut failures: EventReader<NetworkConnectionFailedEvent>,
+    mut disconnects: EventReader<NetworkClientDisconnectedEvent>,
+    mut lost: EventReader<NetworkServerLostEvent>
+) {
+    connections.read().for_each(|event| toasts.info(format!("Player {} connected", event.0)));
+    changes.read().for_each(|event| match event.state {
+        NetworkConnectionState::Connected => toasts.success("Connected to server"),
+        NetworkConnectionState::Hosting => toasts.success("Hosting server"),
+        _ => {}
+    });
+    failures.read().for_each(|event| toasts.error(format!("Could not connect to {}: {}", event.target, event.reason)));
+    disconnects.read().for_each(|event| toasts.info(format!("Player {} disconnected", event.id)));
+    lost.read().for_each(|event| toasts.error(format!("Lost connection to server: {}", event.reason)));
+}
@@ -51,7 +51,7 @@ fn select_text_areas(
     if text_area.selected_border.is_some() { border.0 = text_area.selected_border.unwrap(); }
 }
 
-fn do_typing(
+pub(crate) fn do_typing(
     mut events: ResMut<UIEvents>,
     mut selection: Query<(&mut UITextArea, Option<&UIID>), With<UITextAreaSelected>>,
     mut typing: EventReader<ReceivedCharacter>,
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::node::UINode;
+
+const TOASTS_ID: &str = "toasts";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ToastSeverity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error
+}
+
+impl ToastSeverity {
+    pub fn color(&self) -> Color {
+        match self {
+            ToastSeverity::Info => Color::rgba(0.15, 0.15, 0.15, 0.9),
+            ToastSeverity::Success => Color::rgba(0.1, 0.5, 0.2, 0.9),
+            ToastSeverity::Warning => Color::rgba(0.7, 0.45, 0.05, 0.9),
+            ToastSeverity::Error => Color::rgba(0.65, 0.1, 0.1, 0.9)
+        }
+    }
+}
+
+// where on the screen toasts stack from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ToastPosition {
+    TopLeft,
+    TopCenter,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub text: String,
+    pub severity: ToastSeverity,
+    pub duration: f32,
+    id: u64,
+    elapsed: f32
+}
+
+// resource that holds all queued and shown toasts
+#[derive(Resource, Debug, Clone)]
+pub struct UIToasts {
+    pub position: ToastPosition,
+    pub max_visible: usize,
+    pub default_duration: f32,
+    pub fade_duration: f32,
+    queue: VecDeque<Toast>,
+    active: Vec<Toast>,
+    next_id: u64,
+    dirty: bool
+}
+
+impl Default for UIToasts {
+    fn default() -> Self {
+        Self {
+            position: ToastPosition::default(),
+            max_visible: 5,
+            default_duration: 3.0,
+            fade_duration: 0.5,
+            queue: VecDeque::new(),
+            active: Vec::new(),
+            next_id: 0,
+            dirty: true
+        }
+    }
+}
+
+impl UIToasts {
+    // queue a toast that is shown for the default duration
+    pub fn push(&mut self, text: impl Into<String>, severity: ToastSeverity) {
+        let duration = self.default_duration;
+        self.push_for(text, severity, duration);
+    }
+
+    // queue a toast that is shown for the given number of seconds
+    pub fn push_for(&mut self, text: impl Into<String>, severity: ToastSeverity, duration: f32) {
+        self.queue.push_back(Toast { text: text.into(), severity, duration, id: self.next_id, elapsed: 0.0 });
+        self.next_id += 1;
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) { self.push(text, ToastSeverity::Info); }
+    pub fn success(&mut self, text: impl Into<String>) { self.push(text, ToastSeverity::Success); }
+    pub fn warn(&mut self, text: impl Into<String>) { self.push(text, ToastSeverity::Warning); }
+    pub fn error(&mut self, text: impl Into<String>) { self.push(text, ToastSeverity::Error); }
+
+    // remove all shown and queued toasts
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.active.clear();
+        self.dirty = true;
+    }
+
+    pub fn active(&self) -> &Vec<Toast> { &self.active }
+
+    // change the stack position, rebuilding the toast panel
+    pub fn set_position(&mut self, position: ToastPosition) {
+        self.position = position;
+        self.dirty = true;
+    }
+}
+
+// plugin for toast notifications
+pub struct UIToastsPlugin;
+impl Plugin for UIToastsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<UIToasts>()
+            .add_systems(Update, (update_toasts, render_toasts, fade_toasts).chain());
+    }
+}
+
+fn update_toasts(
+    mut toasts: ResMut<UIToasts>,
+    time: Res<Time>
+) {
+    // tick active toasts and remove finished ones
+    let delta = time.delta_seconds();
+    let before = toasts.active.len();
+    toasts.active.iter_mut().for_each(|toast| toast.elapsed += delta);
+    toasts.active.retain(|toast| toast.elapsed < toast.duration);
+    if toasts.active.len() != before { toasts.dirty = true; }
+
+    // move queued toasts into open slots
+    while toasts.active.len() < toasts.max_visible && !toasts.queue.is_empty() {
+        let toast = toasts.queue.pop_front().unwrap();
+        toasts.active.push(toast);
+        toasts.dirty = true;
+    }
+}
+
+fn render_toasts(
+    mut toasts: ResMut<UIToasts>,
+    mut ui: ResMut<UINode>
+) {
+    if !toasts.dirty { return }
+    toasts.dirty = false;
+
+    // create toast panel if it does not exist yet
+    if ui.get(TOASTS_ID).is_none() {
+        ui.panel().id(TOASTS_ID)
+            .position_type(PositionType::Absolute)
+            .padding(UiRect::all(Val::Px(10.0)))
+            .row_gap(Val::Px(6.0));
+    }
+    let panel = ui.get_mut(TOASTS_ID).unwrap();
+
+    // place panel based on position
+    let (top, bottom) = match toasts.position {
+        ToastPosition::TopLeft | ToastPosition::TopCenter | ToastPosition::TopRight => (Val::Px(0.0), Val::Auto),
+        _ => (Val::Auto, Val::Px(0.0))
+    };
+    let (left, right, align) = match toasts.position {
+        ToastPosition::TopLeft | ToastPosition::BottomLeft => (Val::Px(0.0), Val::Auto, AlignItems::FlexStart),
+        ToastPosition::TopCenter | ToastPosition::BottomCenter => (Val::Px(0.0), Val::Px(0.0), AlignItems::Center),
+        ToastPosition::TopRight | ToastPosition::BottomRight => (Val::Auto, Val::Px(0.0), AlignItems::FlexEnd)
+    };
+    let direction = if bottom == Val::Auto { FlexDirection::Column } else { FlexDirection::ColumnReverse };
+    panel.top(top).bottom(bottom).left(left).right(right).align_items(align).flex_direction(direction);
+
+    // rebuild toasts, newest closest to the edge
+    panel.children.clear();
+    toasts.active.iter().rev().for_each(|toast| {
+        panel.panel().id(format!("toast_{}", toast.id))
+            .bg(toast.severity.color())
+            .padding(UiRect::axes(Val::Px(12.0), Val::Px(6.0)))
+            .children(|ui| { ui.text(toast.text.clone()); });
+    });
+    panel.mark_dirty();
+}
+
+fn fade_toasts(
+    toasts: Res<UIToasts>,
+    ui: Res<UINode>,
+    mut backgrounds: Query<&mut BackgroundColor>,
+    mut texts: Query<&mut Text>
+) {
+    toasts.active.iter().for_each(|toast| {
+        // get how visible the toast should be
+        let remaining = toast.duration - toast.elapsed;
+        let alpha = if toasts.fade_duration > 0.0 { (remaining / toasts.fade_duration).clamp(0.0, 1.0) } else { 1.0 };
+
+        // get toast node, skip if not rendered yet
+        let node = ui.get(format!("toast_{}", toast.id));
+        let node = if node.is_some() { node.unwrap() } else { return };
+
+        // fade background
+        if node.representation.is_some() {
+            let background = backgrounds.get_mut(node.representation.unwrap());
+            if background.is_ok() { background.unwrap().0.set_a(toast.severity.color().a() * alpha); }
+        }
+
+        // fade text
+        node.children.iter().for_each(|child| {
+            if child.representation.is_none() { return }
+            let text = texts.get_mut(child.representation.unwrap());
+            if text.is_ok() { text.unwrap().sections.iter_mut().for_each(|section| { section.style.color.set_a(alpha); }); }
+        });
+    });
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use nebulousengine_input::touch::{TouchControls, TouchRegion};
+
+use crate::node::UINode;
+
+const TOUCH_CONTROLS_ID: &str = "touch_controls";
+
+// plugin that draws the on screen buttons and joysticks of the touch controls resource
+pub struct UITouchControlsPlugin;
+impl Plugin for UITouchControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.world.get_resource_or_insert_with(TouchControls::default);
+        app
+            .init_resource::<UITouchControls>()
+            .add_systems(Update, (render_touch_controls, update_touch_visuals).chain());
+    }
+}
+
+// resource with how touch controls are drawn
+#[derive(Resource, Debug, Clone)]
+pub struct UITouchControls {
+    pub button_color: Color,
+    pub pressed_color: Color,
+    pub joystick_color: Color,
+    pub knob_color: Color,
+    // size of the knob compared to the joysticks radius
+    pub knob_size: f32,
+    // names of the controls currently drawn, rebuilt when they change
+    rendered: Vec<String>
+}
+
+impl Default for UITouchControls {
+    fn default() -> Self {
+        Self {
+            button_color: Color::rgba(1.0, 1.0, 1.0, 0.15),
+            pressed_color: Color::rgba(1.0, 1.0, 1.0, 0.4),
+            joystick_color: Color::rgba(1.0, 1.0, 1.0, 0.1),
+            knob_color: Color::rgba(1.0, 1.0, 1.0, 0.35),
+            knob_size: 0.4,
+            rendered: Vec::new()
+        }
+    }
+}
+
+fn render_touch_controls(
+    mut style: ResMut<UITouchControls>,
+    controls: Res<TouchControls>,
+    mut ui: ResMut<UINode>
+) {
+    // get all controls that should be drawn, stopping if they already are
+    let mut names: Vec<String> = Vec::new();
+    if controls.visible {
+        names.extend(controls.buttons.iter().map(|(name, button)| format!("button {name} {}", button.label)));
+        names.extend(controls.joysticks.iter().map(|(name, joystick)| format!("joystick {name} {}", joystick.radius)));
+        names.sort();
+    }
+    if names == style.rendered && (names.is_empty() || ui.get(TOUCH_CONTROLS_ID).is_some()) { return }
+    style.rendered = names;
+
+    // create full screen panel if it does not exist yet
+    if ui.get(TOUCH_CONTROLS_ID).is_none() {
+        ui.panel().id(TOUCH_CONTROLS_ID)
+            .position_type(PositionType::Absolute)
+            .width(Val::Percent(100.0))
+            .height(Val::Percent(100.0));
+    }
+    let panel = ui.get_mut(TOUCH_CONTROLS_ID).unwrap();
+    panel.children.clear();
+    panel.mark_dirty();
+    if !controls.visible { return }
+
+    // add buttons covering their regions
+    controls.buttons.iter().for_each(|(name, button)| {
+        let node = panel.panel().id(format!("touch_button_{name}"))
+            .position_type(PositionType::Absolute)
+            .bg(if button.pressed { style.pressed_color } else { style.button_color })
+            .align_items(AlignItems::Center)
+            .justify_content(JustifyContent::Center);
+        place_in_region(node, &button.region);
+        node.children(|ui| { ui.text(button.label.clone()); });
+    });
+
+    // add joysticks at the center of their regions, moved to touches by update_touch_visuals
+    controls.joysticks.iter().for_each(|(name, joystick)| {
+        let knob = joystick.radius * style.knob_size;
+        let center = joystick.region.center();
+        panel.panel().id(format!("touch_joystick_{name}"))
+            .position_type(PositionType::Absolute)
+            .left(Val::Percent(center.x * 100.0))
+            .top(Val::Percent(center.y * 100.0))
+            .margin(UiRect::new(Val::Px(-joystick.radius), Val::Auto, Val::Px(-joystick.radius), Val::Auto))
+            .width(Val::Px(joystick.radius * 2.0))
+            .height(Val::Px(joystick.radius * 2.0))
+            .bg(style.joystick_color)
+            .children(|ui| {
+                ui.panel().id(format!("touch_joystick_{name}_knob"))
+                    .position_type(PositionType::Absolute)
+                    .left(Val::Px(joystick.radius - knob))
+                    .top(Val::Px(joystick.radius - knob))
+                    .width(Val::Px(knob * 2.0))
+                    .height(Val::Px(knob * 2.0))
+                    .bg(style.knob_color);
+            });
+    });
+}
+
+fn place_in_region(node: &mut UINode, region: &TouchRegion) {
+    node.left(Val::Percent(region.x * 100.0))
+        .top(Val::Percent(region.y * 100.0))
+        .width(Val::Percent(region.width * 100.0))
+        .height(Val::Percent(region.height * 100.0));
+}
+
+fn update_touch_visuals(
+    style: Res<UITouchControls>,
+    controls: Res<TouchControls>,
+    ui: Res<UINode>,
+    mut nodes: Query<(&mut Style, &mut BackgroundColor)>
+) {
+    if !controls.visible { return }
+
+    // color pressed buttons, changing the entity directly so the node is not rebuilt every frame
+    controls.buttons.iter().for_each(|(name, button)| {
+        let entity = ui.get(format!("touch_button_{name}")).and_then(|node| node.representation);
+        let node = if entity.is_some() { nodes.get_mut(entity.unwrap()) } else { return };
+        let mut node = if node.is_ok() { node.unwrap() } else { return };
+        let color = if button.pressed { style.pressed_color } else { style.button_color };
+        if node.1.0 != color { node.1.0 = color; }
+    });
+
+    // move joysticks to where their touch started and knobs to the joysticks value
+    controls.joysticks.iter().for_each(|(name, joystick)| {
+        let base = ui.get(format!("touch_joystick_{name}")).and_then(|node| node.representation);
+        if base.is_some() && nodes.get_mut(base.unwrap()).is_ok() {
+            let mut base = nodes.get_mut(base.unwrap()).unwrap();
+            let center = joystick.region.center();
+            let (left, top) = match joystick.origin {
+                Some(origin) => (Val::Px(origin.x), Val::Px(origin.y)),
+                None => (Val::Percent(center.x * 100.0), Val::Percent(center.y * 100.0))
+            };
+            if base.0.left != left { base.0.left = left; }
+            if base.0.top != top { base.0.top = top; }
+        }
+
+        let knob = ui.get(format!("touch_joystick_{name}_knob")).and_then(|node| node.representation);
+        if knob.is_some() && nodes.get_mut(knob.unwrap()).is_ok() {
+            let mut knob = nodes.get_mut(knob.unwrap()).unwrap();
+            let offset = joystick.radius - joystick.radius * style.knob_size;
+            let (left, top) = (Val::Px(offset + joystick.value.x * joystick.radius), Val::Px(offset - joystick.value.y * joystick.radius));
+            if knob.0.left != left { knob.0.left = left; }
+            if knob.0.top != top { knob.0.top = top; }
+        }
+    });
+}
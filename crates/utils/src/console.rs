@@ -0,0 +1,262 @@
+use std::{collections::VecDeque, sync::{Arc, Mutex}, marker::PhantomData};
+
+use bevy::{prelude::*, utils::{HashMap, tracing::{self, Subscriber, field::{Field, Visit}}}, log::Level};
+use tracing_log::LogTracer;
+use tracing_subscriber::{prelude::*, registry::Registry, EnvFilter, Layer, layer::Context};
+
+// plugin that sets up the console command registry and runs queued commands
+pub struct ConsolePlugin;
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.world.get_resource_or_insert_with(ConsoleCommands::default);
+        app.world.get_resource_or_insert_with(ConsoleLog::default);
+        app
+            .add_plugins(ConsoleCommandPlugin::<HelpCommand>::default())
+            .add_systems(Update, (execute_console_commands, print_help).chain());
+    }
+}
+
+// a command that can be parsed from a console line and sent as an event
+pub trait ConsoleCommand: Event + Sized {
+    // the first word of the console line that calls this command (ex. "level")
+    fn name() -> &'static str;
+
+    // a short description that is shown by the help command
+    fn description() -> &'static str { "" }
+
+    // parse the arguments that follow the name into this command
+    fn parse(args: &[String]) -> Result<Self, String>;
+
+    // possible completions for the last argument given the arguments before it
+    fn complete(_args: &[String]) -> Vec<String> { Vec::new() }
+}
+
+// plugin that registers a console command and its event
+pub struct ConsoleCommandPlugin<T: ConsoleCommand>(PhantomData<T>);
+impl<T: ConsoleCommand> Default for ConsoleCommandPlugin<T> {
+    fn default() -> Self { Self(PhantomData) }
+}
+
+impl<T: ConsoleCommand> Plugin for ConsoleCommandPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<T>();
+        app.world.get_resource_or_insert_with(ConsoleCommands::default).register::<T>();
+    }
+}
+
+// a registered command, stored without its type so that all commands can live in one map
+#[derive(Debug, Clone, Copy)]
+pub struct ConsoleCommandEntry {
+    pub description: &'static str,
+    run: fn(&mut World, &[String]) -> Result<(), String>,
+    complete: fn(&[String]) -> Vec<String>
+}
+
+// resource containing all registered commands and the lines waiting to be run
+#[derive(Resource, Debug, Default)]
+pub struct ConsoleCommands {
+    pub commands: HashMap<String, ConsoleCommandEntry>,
+    queued: Vec<String>
+}
+
+impl ConsoleCommands {
+    pub fn register<T: ConsoleCommand>(&mut self) {
+        if self.commands.contains_key(T::name()) { warn!("Console command {} registered twice, replacing it!", T::name()); }
+        self.commands.insert(T::name().into(), ConsoleCommandEntry {
+            description: T::description(),
+            run: run_command::<T>,
+            complete: T::complete
+        });
+    }
+
+    // queue a line to be run the next time commands are executed
+    pub fn run(&mut self, line: impl Into<String>) { self.queued.push(line.into()); }
+
+    // get a list of possible full lines that would complete the given line
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        let mut args = split_command(line);
+        if line.is_empty() || line.ends_with(char::is_whitespace) { args.push(String::new()); }
+
+        // if only one word, complete command names
+        if args.len() <= 1 {
+            let start = args.first().cloned().unwrap_or_default();
+            let mut names: Vec<String> = self.commands.keys().filter(|name| name.starts_with(&start)).cloned().collect();
+            names.sort();
+            return names;
+        }
+
+        // otherwise, let the command complete its own arguments
+        let entry = self.commands.get(&args[0]);
+        let entry = if entry.is_some() { entry.unwrap() } else { return Vec::new() };
+        let last = args.last().unwrap().clone();
+        let prefix = args[.. args.len() - 1].join(" ");
+        let mut options: Vec<String> = (entry.complete)(&args[1 .. args.len() - 1]).into_iter()
+            .filter(|option| option.starts_with(&last))
+            .map(|option| format!("{prefix} {option}"))
+            .collect();
+        options.sort();
+        options
+    }
+}
+
+fn run_command<T: ConsoleCommand>(world: &mut World, args: &[String]) -> Result<(), String> {
+    let command = T::parse(args)?;
+    world.send_event(command);
+    Ok(())
+}
+
+// split a console line into words, keeping anything in double quotes together
+pub fn split_command(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    line.chars().for_each(|c| {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() { words.push(std::mem::take(&mut current)); }
+            },
+            _ => current.push(c)
+        }
+    });
+    if !current.is_empty() { words.push(current); }
+    words
+}
+
+// system that runs all queued console lines
+fn execute_console_commands(world: &mut World) {
+    // grab queued lines
+    let queued = std::mem::take(&mut world.resource_mut::<ConsoleCommands>().queued);
+
+    queued.iter().for_each(|line| {
+        // echo line and split it
+        world.resource::<ConsoleLog>().push(Level::INFO, format!("> {line}"));
+        let args = split_command(line);
+        if args.is_empty() { return }
+
+        // find command
+        let entry = world.resource::<ConsoleCommands>().commands.get(&args[0]).copied();
+        let entry = if entry.is_some() { entry.unwrap() } else {
+            world.resource::<ConsoleLog>().push(Level::ERROR, format!("Unknown command \"{}\", try \"help\"", args[0]));
+            return
+        };
+
+        // run command
+        let result = (entry.run)(world, &args[1 ..]);
+        if result.is_err() { world.resource::<ConsoleLog>().push(Level::ERROR, result.err().unwrap()); }
+    });
+}
+
+// built in help command that lists all commands
+#[derive(Event, Debug, Clone)]
+pub struct HelpCommand;
+impl ConsoleCommand for HelpCommand {
+    fn name() -> &'static str { "help" }
+    fn description() -> &'static str { "lists all commands" }
+    fn parse(_args: &[String]) -> Result<Self, String> { Ok(Self) }
+}
+
+fn print_help(
+    mut events: EventReader<HelpCommand>,
+    commands: Res<ConsoleCommands>,
+    log: Res<ConsoleLog>
+) {
+    if events.read().count() == 0 { return }
+    let mut names: Vec<&String> = commands.commands.keys().collect();
+    names.sort();
+    names.iter().for_each(|name| log.push(Level::INFO, format!("{name} - {}", commands.commands[*name].description)));
+}
+
+// a single line in the console log
+#[derive(Debug, Clone)]
+pub struct ConsoleLine {
+    pub level: Level,
+    pub text: String
+}
+
+// resource containing all captured log lines, shared with the tracing layer so logs from any thread end up here
+#[derive(Resource, Debug, Clone)]
+pub struct ConsoleLog {
+    pub max_lines: usize,
+    lines: Arc<Mutex<VecDeque<ConsoleLine>>>,
+    total: Arc<Mutex<usize>>
+}
+
+impl Default for ConsoleLog {
+    fn default() -> Self {
+        Self { max_lines: 500, lines: Arc::default(), total: Arc::default() }
+    }
+}
+
+impl ConsoleLog {
+    pub fn push(&self, level: Level, text: impl Into<String>) {
+        let mut lines = self.lines.lock().unwrap();
+        lines.push_back(ConsoleLine { level, text: text.into() });
+        while lines.len() > self.max_lines { lines.pop_front(); }
+        *self.total.lock().unwrap() += 1;
+    }
+
+    // number of changes ever made to the log, useful to check if it changed
+    pub fn total(&self) -> usize { *self.total.lock().unwrap() }
+
+    pub fn lines(&self) -> Vec<ConsoleLine> { self.lines.lock().unwrap().iter().cloned().collect() }
+
+    // clearing counts as a change so anything showing the log updates
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+        *self.total.lock().unwrap() += 1;
+    }
+}
+
+// replacement for bevy's log plugin that also captures all logs into the console log
+// use with DefaultPlugins.build().disable::<LogPlugin>().add(ConsoleLogPlugin::default())
+pub struct ConsoleLogPlugin {
+    pub filter: String,
+    pub level: Level
+}
+
+impl Default for ConsoleLogPlugin {
+    fn default() -> Self {
+        Self { filter: "wgpu=error,naga=warn".to_string(), level: Level::INFO }
+    }
+}
+
+impl Plugin for ConsoleLogPlugin {
+    fn build(&self, app: &mut App) {
+        let log = app.world.get_resource_or_insert_with(ConsoleLog::default).clone();
+
+        // build subscriber with filter and capture layers
+        let filter = EnvFilter::try_from_default_env()
+            .or_else(|_| EnvFilter::try_new(format!("{},{}", self.level, self.filter)))
+            .unwrap();
+        let subscriber = Registry::default().with(filter).with(ConsoleCaptureLayer(log));
+
+        // still print to stderr where possible
+        #[cfg(not(target_arch = "wasm32"))]
+        let subscriber = subscriber.with(tracing_subscriber::fmt::Layer::default().with_writer(std::io::stderr));
+
+        // set global subscriber
+        if LogTracer::init().is_err() || tracing::subscriber::set_global_default(subscriber).is_err() {
+            warn!("Could not set global logger for the console, make sure bevy's LogPlugin is disabled!");
+        }
+    }
+}
+
+// tracing layer that pushes every event into the console log
+struct ConsoleCaptureLayer(ConsoleLog);
+impl<S: Subscriber> Layer<S> for ConsoleCaptureLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        self.0.push(*event.metadata().level(), visitor.0);
+    }
+}
+
+// visitor that collects the message of a tracing event
+struct MessageVisitor(String);
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" { self.0 = format!("{value:?}{}", self.0); }
+        else { self.0.push_str(&format!(" {}={value:?}", field.name())); }
+    }
+}
@@ -1,13 +1,15 @@
 use bevy::prelude::*;
+use console::ConsolePlugin;
 use follow_camera::FollowCameraPlugin;
 use state_machines::StateMachinePlugin;
 
 pub mod state_machines;
 pub mod follow_camera;
+pub mod console;
 
 pub struct NebulousEngineUtils;
 impl Plugin for NebulousEngineUtils {
     fn build(&self, app: &mut App) {
-        app.add_plugins((FollowCameraPlugin, StateMachinePlugin));
+        app.add_plugins((FollowCameraPlugin, StateMachinePlugin, ConsolePlugin));
     }
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+use bevy::{prelude::*, log::LogPlugin};
+use nebulousengine::NebulousEngine;
+use nebulousengine_utils::console::*;
+
+// command that spawns a given number of cubes (ex. "spawn 5")
+#[derive(Event, Debug, Clone)]
+struct SpawnCommand(usize);
+
+impl ConsoleCommand for SpawnCommand {
+    fn name() -> &'static str { "spawn" }
+    fn description() -> &'static str { "\"spawn <count>\" spawns cubes" }
+
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let count = args.first().map(|a| a.parse::<usize>());
+        match count {
+            Some(Ok(count)) => Ok(Self(count)),
+            _ => Err("Usage: spawn <count>".into())
+        }
+    }
+}
+
+fn main() {
+    App::new()
+        // replace bevy's log plugin so logs show up in the console
+        .add_plugins((DefaultPlugins.build().disable::<LogPlugin>(), ConsoleLogPlugin::default(), NebulousEngine))
+        .add_plugins(ConsoleCommandPlugin::<SpawnCommand>::default())
+        .add_systems(Startup, setup)
+        .add_systems(Update, spawn)
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-2.5, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    info!("Press ` to open the console, then try \"help\" or \"spawn 3\"");
+}
+
+fn spawn(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut events: EventReader<SpawnCommand>
+) {
+    events.read().for_each(|event| {
+        (0 .. event.0).for_each(|i| {
+            commands.spawn(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Cube { size: 0.5 })),
+                material: materials.add(Color::rgb_u8(124, 144, 255).into()),
+                transform: Transform::from_xyz(i as f32, 0.25, 0.0),
+                ..default()
+            });
+        });
+        info!("Spawned {} cubes", event.0);
+    });
+}
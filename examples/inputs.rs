@@ -1,35 +1,70 @@
 use bevy::prelude::*;
 use nebulousengine::NebulousEngine;
-use nebulousengine_input::structs::*;
-use nebulousengine_ui::node::*;
+use nebulousengine_input::{input_actions, actions::*, structs::*, mouse::MouseAxis, rebind::InputCapturedEvent, interactions::*, contexts::*, recording::*};
+use nebulousengine_ui::{node::*, input_prompts::UIInputPrompts};
 
 fn main() {
     App::new()
-        .add_plugins((DefaultPlugins, NebulousEngine))
+        .add_plugins((DefaultPlugins, NebulousEngine, InputActionPlugin::<EditorAction>::default()))
         .add_systems(Startup, setup)
-        .add_systems(Update, update)
+        .add_systems(Update, (update, rebind, interactions, pause, record, save))
         .run();
 }
 
+// typed actions, checked at compile time instead of using strings
+input_actions! {
+    enum EditorAction {
+        Save = "save"
+    }
+}
+
 fn setup(
     mut commands: Commands,
-    mut ui: ResMut<UINode>
+    mut ui: ResMut<UINode>,
+    mut prompts: ResMut<UIInputPrompts>
 ) {
     commands.spawn(Camera2dBundle::default());
 
     // create input description
     let description = InputDescription::create(|inputs| {
         inputs.insert("vertical", vec![
-            InputType::AXIS { positive: InputElement::Keyboard { key: KeyCode::W }, negative: InputElement::Keyboard { key: KeyCode::S } },
-            InputType::SCALAR { element: InputElement::GamepadAxis { axis: GamepadAxisType::LeftStickY, mult: 1.0 } }
+            InputType::axis(InputElement::Keyboard { key: KeyCode::W }, InputElement::Keyboard { key: KeyCode::S }),
+            InputType::scalar(InputElement::GamepadAxis { axis: GamepadAxisType::LeftStickY, mult: 1.0 })
         ]);
         inputs.insert("horizontal", vec![
-            InputType::AXIS { positive: InputElement::Keyboard { key: KeyCode::D }, negative: InputElement::Keyboard { key: KeyCode::A } },
-            InputType::SCALAR { element: InputElement::GamepadAxis { axis: GamepadAxisType::LeftStickX, mult: 1.0 } }
+            InputType::axis(InputElement::Keyboard { key: KeyCode::D }, InputElement::Keyboard { key: KeyCode::A }),
+            InputType::scalar(InputElement::GamepadAxis { axis: GamepadAxisType::LeftStickX, mult: 1.0 })
+        ]);
+        inputs.insert("move", vec![
+            InputType::vec2(
+                InputType::axis(InputElement::Keyboard { key: KeyCode::D }, InputElement::Keyboard { key: KeyCode::A }),
+                InputType::axis(InputElement::Keyboard { key: KeyCode::W }, InputElement::Keyboard { key: KeyCode::S })
+            ),
+            InputType::VEC2 {
+                x: Box::new(InputType::scalar(InputElement::GamepadAxis { axis: GamepadAxisType::LeftStickX, mult: 1.0 })),
+                y: Box::new(InputType::scalar(InputElement::GamepadAxis { axis: GamepadAxisType::LeftStickY, mult: 1.0 })),
+                dead_zone: 0.15,
+                interactions: Vec::new()
+            }
+        ]);
+        inputs.insert("look_x", vec![
+            InputType::scalar(InputElement::MouseMotion { axis: MouseAxis::X, sensitivity: 0.05 }),
+            InputType::scalar(InputElement::GamepadAxis { axis: GamepadAxisType::RightStickX, mult: 1.0 }.with_dead_zone(0.1).with_curve(ResponseCurve::Power { exponent: 2.0 }))
+        ]);
+        inputs.insert("zoom", vec![
+            InputType::scalar(InputElement::MouseWheel { axis: MouseAxis::Y, mult: 1.0 })
+        ]);
+        inputs.insert_action(EditorAction::Save, vec![
+            InputType::chord(vec![InputElement::Keyboard { key: KeyCode::ControlLeft }, InputElement::Keyboard { key: KeyCode::S }])
+        ]);
+        inputs.insert("interact", vec![
+            InputType::scalar(InputElement::Keyboard { key: KeyCode::E })
+                .with_interaction(InputInteraction::Hold { duration: 0.5, threshold: 0.5 })
+                .with_interaction(InputInteraction::MultiTap { count: 2, window: 0.25, threshold: 0.5 })
         ]);
         inputs.insert("click", vec![
-            InputType::SCALAR { element: InputElement::Mouse { button: MouseButton::Left } },
-            InputType::SCALAR { element: InputElement::GamepadButton { button: GamepadButtonType::North } }
+            InputType::scalar(InputElement::Mouse { button: MouseButton::Left }),
+            InputType::scalar(InputElement::GamepadButton { button: GamepadButtonType::North })
         ]);
     });
 
@@ -47,8 +82,12 @@ fn setup(
             });
     });
 
+    // show which button interacts for the last used device
+    panel.text("").id("interact_prompt");
+    prompts.text("interact_prompt", "interact", "Press {} to interact");
+
     // spawn input description
-    commands.spawn(Inputs::from_description(description));
+    commands.spawn((Inputs::from_description(description), Name::new("player")));
 }
 
 fn update(
@@ -61,3 +100,76 @@ fn update(
         ui.get_mut(name).unwrap().set_text(format!("{}", value));
     });
 }
+
+// press F1 to rebind click to the next pressed input
+fn rebind(
+    mut inputs: Query<&mut Inputs>,
+    keys: Res<Input<KeyCode>>,
+    mut captured: EventReader<InputCapturedEvent>
+) {
+    if keys.just_pressed(KeyCode::F1) {
+        inputs.for_each_mut(|mut inputs| inputs.start_capture("click", InputCaptureMode::Replace));
+        println!("Press something to bind to click...");
+    }
+
+    captured.read().for_each(|event| {
+        println!("Bound {:?} to {}, conflicts: {:?}", event.element, event.action, event.conflicts);
+    });
+}
+
+// print hold and double tap interactions
+fn interactions(
+    mut holds: EventReader<InputHoldEvent>,
+    mut taps: EventReader<InputMultiTapEvent>
+) {
+    holds.read().for_each(|event| println!("Held {} for {}s", event.name, event.duration));
+    taps.read().for_each(|event| println!("Tapped {} {} times", event.name, event.count));
+}
+
+// press P to push a pause context that only lets click through to the base description
+fn pause(
+    mut inputs: Query<&mut Inputs>,
+    keys: Res<Input<KeyCode>>
+) {
+    if !keys.just_pressed(KeyCode::P) { return }
+    inputs.for_each_mut(|mut inputs| {
+        if inputs.pop_context("paused").is_some() { println!("Unpaused"); return }
+        inputs.push_context(InputContext::new("paused", 10, |inputs| {
+            inputs.insert("click", vec![InputType::scalar(InputElement::Mouse { button: MouseButton::Left })]);
+        }).with_consume(InputConsume::All));
+        println!("Paused");
+    });
+}
+
+// press F5 to start and stop recording, and F6 to replay the last recording
+fn record(
+    mut recorder: ResMut<InputRecorder>,
+    keys: Res<Input<KeyCode>>,
+    mut mismatches: EventReader<InputPlaybackMismatchEvent>,
+    mut finished: EventReader<InputPlaybackFinishedEvent>
+) {
+    if keys.just_pressed(KeyCode::F5) && !recorder.is_playing() {
+        if recorder.is_recording() {
+            let recording = recorder.stop_recording().unwrap();
+            if recording.save("inputs.recording").is_ok() { println!("Saved {} frames to inputs.recording", recording.frames.len()); }
+        } else {
+            recorder.start_recording();
+            println!("Recording...");
+        }
+    }
+
+    if keys.just_pressed(KeyCode::F6) && !recorder.is_recording() && !recorder.is_playing() {
+        match InputRecording::load("inputs.recording") {
+            Ok(recording) => { recorder.play(recording); println!("Playing inputs.recording"); },
+            Err(_) => println!("No recording to play, press F5 to record one")
+        }
+    }
+
+    mismatches.read().for_each(|event| println!("Frame {}: {} was {} but recorded {}", event.frame, event.name, event.actual, event.expected));
+    finished.read().for_each(|event| println!("Finished playing {} frames", event.frames));
+}
+
+// print typed save events with the inputs entity that sent them
+fn save(mut events: EventReader<InputActionPressedEvent<EditorAction>>) {
+    events.read().for_each(|event| println!("{:?} pressed by {:?}", event.action, event.entity));
+}
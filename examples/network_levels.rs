@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 use nebulousengine::NebulousEngine;
 use nebulousengine_levels::levels::Level;
-use nebulousengine_networking::{*, levels::*};
+use nebulousengine_networking::{*, levels::*, connection::NetworkConfig};
+use nebulousengine_ui::network_toasts::UINetworkToastsPlugin;
 use serde::*;
 
 #[derive(States, Default, Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -11,9 +12,17 @@ enum TestLevels {
     TestB
 }
 
+// run with "host" or "join" to pick a role, otherwise the first app to start hosts
 fn main() {
+    let config = match std::env::args().nth(1).as_deref() {
+        Some("host") => NetworkConfig::host("127.0.0.1:8050"),
+        Some("join") => NetworkConfig::join("ws://127.0.0.1:8050"),
+        _ => NetworkConfig::default()
+    };
+
     App::new()
-        .add_plugins((DefaultPlugins, NebulousEngine, GameNetworkingPlugin, SyncedLevel::<TestLevels>::default()))
+        .insert_resource(config)
+        .add_plugins((DefaultPlugins, NebulousEngine, GameNetworkingPlugin, SyncedLevel::<TestLevels>::default(), UINetworkToastsPlugin))
         .add_systems(Startup, setup)
         .add_systems(Update, update)
         .add_systems(OnEnter(TestLevels::TestA), start_a)
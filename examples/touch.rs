@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use nebulousengine::NebulousEngine;
+use nebulousengine_input::{structs::*, touch::*};
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, NebulousEngine))
+        .add_systems(Startup, setup)
+        .add_systems(Update, update)
+        .run();
+}
+
+#[derive(Component)]
+struct Player;
+
+fn setup(
+    mut commands: Commands,
+    mut controls: ResMut<TouchControls>,
+    asset_server: Res<AssetServer>
+) {
+    commands.spawn(Camera2dBundle::default());
+
+    // the same input file binds keyboard, gamepad and touch controls to each action
+    commands.spawn((
+        Inputs::from_handle(asset_server.load("touch.input")),
+        Player,
+        SpriteBundle { sprite: Sprite { custom_size: Some(Vec2::splat(50.0)), ..Default::default() }, ..Default::default() }
+    ));
+
+    // move with a joystick on the left half of the screen and jump with a button in the bottom right
+    controls
+        .add_joystick("move", TouchRegion::new(0.0, 0.3, 0.5, 0.7), 80.0)
+        .add_button("jump", TouchRegion::new(0.75, 0.7, 0.2, 0.25), "Jump");
+
+    // drag with the mouse to test without a touch screen
+    controls.emulate_with_mouse = true;
+    controls.visible = true;
+}
+
+fn update(
+    mut player: Query<(&Inputs, &mut Transform), With<Player>>,
+    time: Res<Time>
+) {
+    let player = player.get_single_mut();
+    let (inputs, mut transform) = if player.is_ok() { player.unwrap() } else { return };
+    transform.translation += (inputs.get_vec2(&"move".to_string()) * 300.0 * time.delta_seconds()).extend(0.0);
+    transform.scale = Vec3::splat(if inputs.get(&"jump".to_string()) >= 1.0 { 1.5 } else { 1.0 });
+}